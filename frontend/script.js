@@ -42,6 +42,10 @@ async function loadTargets() {
     targets.forEach(t => {
       const li = document.createElement('li');
       li.innerHTML = `<span class="badge">#${t.id}</span> <span>${t.url}</span>`;
+      if (t.enabled === false) {
+        li.classList.add('paused');
+        li.insertAdjacentHTML('beforeend', '<span class="badge">paused</span>');
+      }
       li.addEventListener('click', () => loadTargetStatus(t));
       targetsListEl.appendChild(li);
     });
@@ -98,8 +102,8 @@ function renderChart(target, records) {
 async function loadTargetStatus(target) {
   chartTitleEl.textContent = `Metrics for ${target.url}`;
   try {
-    const records = await fetchJSON(`${API_BASE}/api/status/${target.id}`);
-    renderChart(target, records.reverse()); // draw oldest -> newest
+    const page = await fetchJSON(`${API_BASE}/api/status/${target.id}`);
+    renderChart(target, page.records.reverse()); // draw oldest -> newest
   } catch (e) {
     chartTitleEl.textContent = `Failed to load metrics for ${target.url}: ${e.message}`;
   }
@@ -0,0 +1,71 @@
+-- Initial schema for DevOps Health Monitor. Databases created before migrations were introduced
+-- already have some of these objects, so every statement is idempotent.
+
+CREATE TABLE IF NOT EXISTS targets (
+    id SERIAL PRIMARY KEY,
+    url TEXT NOT NULL UNIQUE
+);
+
+CREATE TABLE IF NOT EXISTS health_checks (
+    id SERIAL PRIMARY KEY,
+    target_id INTEGER NOT NULL REFERENCES targets(id) ON DELETE CASCADE,
+    checked_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    status_code INTEGER,
+    response_time_ms INTEGER
+);
+
+-- Helpful index for querying recent health checks per target
+CREATE INDEX IF NOT EXISTS idx_health_checks_target_checked_at
+ON health_checks (target_id, checked_at DESC);
+
+-- Per-target settings added after the initial release
+ALTER TABLE targets ADD COLUMN IF NOT EXISTS check_interval_secs INTEGER DEFAULT 60;
+-- NULL defers to the worker's CHECK_INTERVAL_SECS
+ALTER TABLE targets ALTER COLUMN check_interval_secs DROP DEFAULT;
+ALTER TABLE targets ADD COLUMN IF NOT EXISTS timeout_ms INTEGER;
+ALTER TABLE targets ADD COLUMN IF NOT EXISTS expected_status INTEGER DEFAULT 200;
+ALTER TABLE targets ADD COLUMN IF NOT EXISTS check_type TEXT NOT NULL DEFAULT 'http';
+ALTER TABLE targets ADD COLUMN IF NOT EXISTS headers JSONB;
+ALTER TABLE targets ADD COLUMN IF NOT EXISTS follow_redirects BOOLEAN NOT NULL DEFAULT true;
+ALTER TABLE health_checks ADD COLUMN IF NOT EXISTS redirect_count INTEGER;
+ALTER TABLE targets ADD COLUMN IF NOT EXISTS expected_body_substring TEXT;
+ALTER TABLE health_checks ADD COLUMN IF NOT EXISTS body_match BOOLEAN;
+ALTER TABLE targets ADD COLUMN IF NOT EXISTS cert_expires_at TIMESTAMPTZ;
+ALTER TABLE targets ADD COLUMN IF NOT EXISTS http_method TEXT NOT NULL DEFAULT 'GET';
+ALTER TABLE targets ADD COLUMN IF NOT EXISTS request_body TEXT;
+ALTER TABLE targets ADD COLUMN IF NOT EXISTS enabled BOOLEAN NOT NULL DEFAULT true;
+ALTER TABLE health_checks ADD COLUMN IF NOT EXISTS dns_ms INTEGER;
+ALTER TABLE targets ADD COLUMN IF NOT EXISTS tags TEXT[] NOT NULL DEFAULT '{}';
+ALTER TABLE targets ADD COLUMN IF NOT EXISTS consecutive_failures INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE targets ADD COLUMN IF NOT EXISTS expected_body_regex TEXT;
+ALTER TABLE targets ADD COLUMN IF NOT EXISTS expected_content_type TEXT;
+ALTER TABLE health_checks ADD COLUMN IF NOT EXISTS content_type_match BOOLEAN;
+ALTER TABLE targets ADD COLUMN IF NOT EXISTS latency_ema_ms DOUBLE PRECISION;
+ALTER TABLE targets ADD COLUMN IF NOT EXISTS basic_auth_user TEXT;
+ALTER TABLE targets ADD COLUMN IF NOT EXISTS basic_auth_pass TEXT;
+ALTER TABLE health_checks ADD COLUMN IF NOT EXISTS region TEXT NOT NULL DEFAULT 'default';
+ALTER TABLE targets ADD COLUMN IF NOT EXISTS latency_threshold_ms INTEGER;
+-- Whether the latest healthy check exceeded latency_threshold_ms, to alert only on the transition
+ALTER TABLE targets ADD COLUMN IF NOT EXISTS slow BOOLEAN NOT NULL DEFAULT false;
+-- Name shown on the public status feed instead of the host
+ALTER TABLE targets ADD COLUMN IF NOT EXISTS display_name TEXT;
+-- Protocol negotiated for HTTP checks, e.g. HTTP/1.1 or HTTP/2
+ALTER TABLE health_checks ADD COLUMN IF NOT EXISTS http_version TEXT;
+
+-- Supports the retention job's range delete on checked_at
+CREATE INDEX IF NOT EXISTS idx_health_checks_checked_at ON health_checks (checked_at);
+
+-- Supports filtering targets by tag (tags @> ARRAY[...])
+CREATE INDEX IF NOT EXISTS idx_targets_tags ON targets USING GIN (tags);
+
+-- Scheduled maintenance: checks keep running but alerts are suppressed inside a window
+CREATE TABLE IF NOT EXISTS maintenance_windows (
+    id SERIAL PRIMARY KEY,
+    target_id INTEGER NOT NULL REFERENCES targets(id) ON DELETE CASCADE,
+    starts_at TIMESTAMPTZ NOT NULL,
+    ends_at TIMESTAMPTZ NOT NULL,
+    CHECK (ends_at > starts_at)
+);
+
+CREATE INDEX IF NOT EXISTS idx_maintenance_windows_target_ends_at
+ON maintenance_windows (target_id, ends_at);
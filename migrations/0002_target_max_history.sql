@@ -0,0 +1,2 @@
+-- Only the newest max_history checks are kept per target; NULL keeps them all until RETENTION_DAYS
+ALTER TABLE targets ADD COLUMN max_history INTEGER;
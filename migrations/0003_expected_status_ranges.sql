@@ -0,0 +1,18 @@
+-- Accepted status codes such as '200-299,301,418', stored in canonical form; takes precedence over expected_status
+ALTER TABLE targets ADD COLUMN expected_status_ranges TEXT;
+
+-- Whether a check's status code meets its target's expectation, mirroring `is_healthy`: any of
+-- the ranges when set, otherwise the single expected code, otherwise any 2xx
+CREATE OR REPLACE FUNCTION status_matches(code INTEGER, expected INTEGER, ranges TEXT) RETURNS BOOLEAN
+LANGUAGE sql IMMUTABLE AS $$
+    SELECT CASE
+        WHEN ranges IS NOT NULL THEN EXISTS (
+            SELECT 1
+            FROM unnest(string_to_array(ranges, ',')) AS part
+            WHERE code BETWEEN split_part(part, '-', 1)::integer
+                           AND COALESCE(NULLIF(split_part(part, '-', 2), ''), part)::integer
+        )
+        WHEN expected IS NULL THEN code BETWEEN 200 AND 299
+        ELSE code = expected
+    END
+$$;
@@ -0,0 +1,9 @@
+-- Round-trip time of a `SELECT 1` from the worker, sampled every tick, to chart the monitor's own database
+CREATE TABLE db_latency_samples (
+    id BIGSERIAL PRIMARY KEY,
+    checked_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    latency_ms DOUBLE PRECISION NOT NULL,
+    region TEXT NOT NULL
+);
+
+CREATE INDEX idx_db_latency_samples_checked_at ON db_latency_samples (checked_at);
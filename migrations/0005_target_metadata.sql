@@ -0,0 +1,4 @@
+-- Schema-less key-value metadata such as an owner or runbook link, filtered with JSONB containment
+ALTER TABLE targets ADD COLUMN metadata JSONB NOT NULL DEFAULT '{}';
+
+CREATE INDEX idx_targets_metadata ON targets USING GIN (metadata jsonb_path_ops);
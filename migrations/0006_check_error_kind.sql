@@ -0,0 +1,2 @@
+-- Why a check got no response (timeout, dns, connect, tls, ...); NULL when the target responded
+ALTER TABLE health_checks ADD COLUMN error_kind TEXT;
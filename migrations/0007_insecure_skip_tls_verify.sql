@@ -0,0 +1,2 @@
+-- Accept self-signed or otherwise invalid certificates when checking this target
+ALTER TABLE targets ADD COLUMN insecure_skip_tls_verify BOOLEAN NOT NULL DEFAULT false;
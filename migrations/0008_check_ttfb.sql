@@ -0,0 +1,2 @@
+-- Time until the final response's headers arrived, as opposed to response_time_ms which includes the body
+ALTER TABLE health_checks ADD COLUMN ttfb_ms INTEGER;
@@ -0,0 +1,12 @@
+-- Every alert delivery attempt, one row per sink, as an audit trail of notifications
+CREATE TABLE alerts (
+    id BIGSERIAL PRIMARY KEY,
+    target_id INTEGER NOT NULL REFERENCES targets(id) ON DELETE CASCADE,
+    kind TEXT NOT NULL,
+    channel TEXT NOT NULL,
+    sent_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    delivery_ok BOOLEAN NOT NULL
+);
+
+CREATE INDEX idx_alerts_target_sent_at ON alerts (target_id, sent_at DESC);
+CREATE INDEX idx_alerts_sent_at ON alerts (sent_at DESC);
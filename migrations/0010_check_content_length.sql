@@ -0,0 +1,2 @@
+-- Bytes of response body drained by an HTTP check, to chart payload size over time
+ALTER TABLE health_checks ADD COLUMN content_length_bytes BIGINT;
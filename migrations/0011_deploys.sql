@@ -0,0 +1,9 @@
+-- Deploy markers reported by CI through POST /api/targets/:target_id/deployed
+CREATE TABLE deploys (
+    id BIGSERIAL PRIMARY KEY,
+    target_id INTEGER NOT NULL REFERENCES targets(id) ON DELETE CASCADE,
+    deployed_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    details JSONB
+);
+
+CREATE INDEX idx_deploys_target_deployed_at ON deploys (target_id, deployed_at DESC);
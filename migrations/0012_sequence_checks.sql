@@ -0,0 +1,3 @@
+-- Ordered requests run by `sequence` checks, and which of them failed a check
+ALTER TABLE targets ADD COLUMN steps JSONB;
+ALTER TABLE health_checks ADD COLUMN failed_step INTEGER;
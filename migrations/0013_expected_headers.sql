@@ -0,0 +1,3 @@
+-- Response headers a target must send, by lower-case name; an empty value only requires presence
+ALTER TABLE targets ADD COLUMN expected_headers JSONB;
+ALTER TABLE health_checks ADD COLUMN headers_match BOOLEAN;
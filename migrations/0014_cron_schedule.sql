@@ -0,0 +1,2 @@
+-- Cron expression (UTC) scheduling a target's checks in place of its interval
+ALTER TABLE targets ADD COLUMN cron_schedule TEXT;
@@ -0,0 +1,2 @@
+-- Whether the target is burning its error budget fast enough to have raised a burn-rate alert
+ALTER TABLE targets ADD COLUMN burning BOOLEAN NOT NULL DEFAULT false;
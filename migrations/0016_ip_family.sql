@@ -0,0 +1,3 @@
+-- Address family a target's checks connect over ('auto', 'v4', or 'v6'), and the address each check reached
+ALTER TABLE targets ADD COLUMN ip_family TEXT NOT NULL DEFAULT 'auto';
+ALTER TABLE health_checks ADD COLUMN resolved_ip TEXT;
@@ -0,0 +1,7 @@
+-- Service-wide settings, in a single row: maintenance mode stops every check and alert
+CREATE TABLE service_config (
+    id BOOLEAN PRIMARY KEY DEFAULT true CHECK (id),
+    maintenance BOOLEAN NOT NULL DEFAULT false,
+    maintenance_since TIMESTAMPTZ
+);
+INSERT INTO service_config DEFAULT VALUES;
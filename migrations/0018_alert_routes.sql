@@ -0,0 +1,13 @@
+-- Alert destinations by target tag and/or metadata severity; targets matching any route alert
+-- every matching destination instead of the default sinks
+CREATE TABLE alert_routes (
+    id SERIAL PRIMARY KEY,
+    tag TEXT,
+    severity TEXT,
+    webhook_url TEXT,
+    template TEXT,
+    email TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (tag IS NOT NULL OR severity IS NOT NULL),
+    CHECK ((webhook_url IS NULL) <> (email IS NULL))
+);
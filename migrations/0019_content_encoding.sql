@@ -0,0 +1,4 @@
+-- Content-Encoding a target's responses must carry (e.g. 'gzip'), and the encoding each check received
+ALTER TABLE targets ADD COLUMN expected_content_encoding TEXT;
+ALTER TABLE health_checks ADD COLUMN content_encoding TEXT;
+ALTER TABLE health_checks ADD COLUMN encoding_match BOOLEAN;
@@ -0,0 +1,2 @@
+-- When cert_expires_at was last refreshed, so certificates are inspected daily rather than on every check
+ALTER TABLE targets ADD COLUMN cert_checked_at TIMESTAMPTZ;
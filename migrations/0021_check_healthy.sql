@@ -0,0 +1,12 @@
+-- Whether a check counts as healthy for its target, mirroring `is_check_healthy`: the status meets
+-- the target's expectation and no content assertion (NULL when not configured) failed. Queries
+-- call this instead of spelling the rule out, so a new assertion only needs adding here.
+CREATE OR REPLACE FUNCTION check_healthy(h health_checks, t targets) RETURNS BOOLEAN
+LANGUAGE sql IMMUTABLE AS $$
+    SELECT COALESCE(
+        status_matches(h.status_code, t.expected_status, t.expected_status_ranges)
+        AND h.body_match IS NOT FALSE AND h.content_type_match IS NOT FALSE
+        AND h.headers_match IS NOT FALSE AND h.encoding_match IS NOT FALSE,
+        false
+    )
+$$;
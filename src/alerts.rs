@@ -0,0 +1,424 @@
+//! Alerting on target status transitions (healthy → unhealthy and back).
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use futures::future::join_all;
+use lettre::{
+    message::Mailbox, transport::smtp::AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+use serde::Deserialize;
+use serde_json::json;
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+
+/// The transition an alert reports.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AlertKind {
+    Down,
+    Up,
+    /// Responding, but slower than the target's `latency_threshold_ms`.
+    Slow,
+    /// Reminder that a target is still down once its alert cooldown has passed.
+    StillDown,
+    /// Failing fast enough to use up its error budget well ahead of the SLO period.
+    BurnRate,
+}
+
+impl AlertKind {
+    fn label(self) -> &'static str {
+        match self {
+            AlertKind::Down => "DOWN",
+            AlertKind::Up => "UP",
+            AlertKind::Slow => "SLOW",
+            AlertKind::StillDown => "STILL DOWN",
+            AlertKind::BurnRate => "BURN RATE",
+        }
+    }
+
+    /// How the kind is stored in the `alerts` table.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AlertKind::Down => "down",
+            AlertKind::Up => "up",
+            AlertKind::Slow => "slow",
+            AlertKind::StillDown => "still_down",
+            AlertKind::BurnRate => "burn_rate",
+        }
+    }
+}
+
+/// A single status transition worth notifying about.
+#[derive(Debug)]
+pub struct Alert<'a> {
+    pub kind: AlertKind,
+    pub url: &'a str,
+    pub status: Option<i32>,
+    pub latency_ms: Option<i32>,
+    /// The target's metadata, e.g. an owner or runbook link to act on the alert.
+    pub metadata: &'a crate::Metadata,
+}
+
+impl Alert<'_> {
+    fn status_text(&self) -> String {
+        self.status.map_or_else(|| "no response".to_string(), |code| code.to_string())
+    }
+
+    fn latency_text(&self) -> String {
+        self.latency_ms.map_or_else(|| "n/a".to_string(), |ms| ms.to_string())
+    }
+
+    /// One `key: value` line per metadata entry, strings unquoted.
+    fn metadata_lines(&self) -> String {
+        self.metadata
+            .iter()
+            .map(|(key, value)| match value {
+                serde_json::Value::String(text) => format!("{key}: {text}\n"),
+                other => format!("{key}: {other}\n"),
+            })
+            .collect()
+    }
+
+    fn summary(&self) -> String {
+        let status = self
+            .status
+            .map_or_else(|| "no response".to_string(), |code| format!("status {code}"));
+        let latency = self
+            .latency_ms
+            .map_or_else(|| "n/a".to_string(), |ms| format!("{ms} ms"));
+        let summary = format!("[{}] {} ({status}, latency {latency})", self.kind.label(), self.url);
+        if self.metadata.is_empty() {
+            summary
+        } else {
+            format!("{summary}\n{}", self.metadata_lines().trim_end())
+        }
+    }
+
+    fn email_subject(&self) -> String {
+        let outcome = match (self.kind, self.status, self.latency_ms) {
+            (AlertKind::Slow, _, Some(ms)) => format!("took {ms} ms"),
+            (AlertKind::BurnRate, _, _) => "is burning its error budget".to_string(),
+            (_, Some(code), _) => format!("returned {code}"),
+            (_, None, _) => "did not respond".to_string(),
+        };
+        format!("[{}] {} {outcome}", self.kind.label(), self.url)
+    }
+
+    fn email_body(&self, at: DateTime<Utc>) -> String {
+        let latency = self
+            .latency_ms
+            .map_or_else(|| "n/a".to_string(), |ms| format!("{ms} ms"));
+        format!(
+            "Target: {}\nStatus: {}\nLatency: {latency}\nTimestamp: {}\n{}",
+            self.url,
+            self.status_text(),
+            at.to_rfc3339(),
+            self.metadata_lines(),
+        )
+    }
+}
+
+/// Whether an alert reached one sink (`slack`, `webhook`, or `email`).
+#[derive(Debug)]
+pub struct Delivery {
+    pub channel: &'static str,
+    pub ok: bool,
+}
+
+/// Results of [`Alerter::notify`]. The email is still being sent when `notify` returns, so its
+/// outcome arrives separately.
+pub struct Deliveries {
+    pub sent: Vec<Delivery>,
+    pub email: Option<JoinHandle<bool>>,
+}
+
+/// An SMTP sink, configured through the `SMTP_URL`, `ALERT_FROM`, and `ALERT_TO` secrets.
+#[derive(Clone)]
+struct EmailSink {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    to: Vec<Mailbox>,
+}
+
+impl EmailSink {
+    /// Returns `None` when none of the variables are set, and an error when only some are.
+    fn from_env() -> anyhow::Result<Option<Self>> {
+        let var = |key| std::env::var(key).ok().filter(|value: &String| !value.trim().is_empty());
+        let (url, from, to) = match (var("SMTP_URL"), var("ALERT_FROM"), var("ALERT_TO")) {
+            (None, None, None) => return Ok(None),
+            (Some(url), Some(from), Some(to)) => (url, from, to),
+            _ => anyhow::bail!("SMTP_URL, ALERT_FROM, and ALERT_TO must be set together to enable email alerts"),
+        };
+
+        let transport = AsyncSmtpTransport::<Tokio1Executor>::from_url(&url)
+            .context("invalid SMTP_URL")?
+            .timeout(Some(Duration::from_secs(30)))
+            .build();
+        let from = from.parse().with_context(|| format!("invalid ALERT_FROM address '{from}'"))?;
+        let to = to
+            .split(',')
+            .map(|addr| addr.trim().parse().with_context(|| format!("invalid ALERT_TO address '{}'", addr.trim())))
+            .collect::<anyhow::Result<Vec<Mailbox>>>()?;
+        Ok(Some(Self { transport, from, to }))
+    }
+
+    fn message(&self, alert: &Alert<'_>, at: DateTime<Utc>) -> anyhow::Result<Message> {
+        let builder = self
+            .to
+            .iter()
+            .fold(Message::builder().from(self.from.clone()), |builder, to| builder.to(to.clone()));
+        Ok(builder.subject(alert.email_subject()).body(alert.email_body(at))?)
+    }
+}
+
+/// A generic webhook sink, configured through the `ALERT_WEBHOOKS` secret.
+#[derive(Clone, Debug, Deserialize)]
+pub struct WebhookTarget {
+    pub url: String,
+    /// JSON body with `{{kind}}`, `{{url}}`, `{{status}}`, `{{latency}}`, and `{{timestamp}}`
+    /// placeholders; a default JSON payload is sent when omitted.
+    pub template: Option<String>,
+}
+
+impl WebhookTarget {
+    /// Renders the request body. Substituted values are JSON-escaped, so placeholders belong
+    /// inside string literals in the template.
+    fn render(&self, alert: &Alert<'_>, at: DateTime<Utc>) -> String {
+        let Some(template) = &self.template else {
+            return json!({
+                "kind": alert.kind.label(),
+                "url": alert.url,
+                "status": alert.status,
+                "latency_ms": alert.latency_ms,
+                "timestamp": at.to_rfc3339(),
+                "metadata": alert.metadata,
+            })
+            .to_string();
+        };
+        [
+            ("{{kind}}", alert.kind.label().to_string()),
+            ("{{url}}", alert.url.to_string()),
+            ("{{status}}", alert.status_text()),
+            ("{{latency}}", alert.latency_text()),
+            ("{{timestamp}}", at.to_rfc3339()),
+        ]
+        .iter()
+        .fold(template.clone(), |body, (placeholder, value)| {
+            body.replace(placeholder, &json_escape(value))
+        })
+    }
+}
+
+/// Escapes `value` for use inside a JSON string literal.
+fn json_escape(value: &str) -> String {
+    let quoted = serde_json::Value::from(value).to_string();
+    quoted[1..quoted.len() - 1].to_string()
+}
+
+/// Where a routed alert goes, from a matching `alert_routes` row. Targets with any matching route
+/// are alerted at those destinations instead of the default sinks.
+#[derive(Clone, Debug)]
+pub enum Destination {
+    Webhook(WebhookTarget),
+    /// Sent through the configured SMTP server, to this address instead of `ALERT_TO`.
+    Email(Mailbox),
+}
+
+impl Destination {
+    /// Checks a route's destination the way `ALERT_WEBHOOKS` entries are checked: exactly one of
+    /// a webhook (with its optional template) or an email address.
+    pub fn new(webhook_url: Option<&str>, template: Option<&str>, email: Option<&str>) -> anyhow::Result<Self> {
+        match (webhook_url, email) {
+            (Some(url), None) => {
+                let webhook = WebhookTarget { url: url.to_string(), template: template.map(str::to_string) };
+                validate_webhook(&webhook)?;
+                Ok(Destination::Webhook(webhook))
+            }
+            (None, Some(email)) if template.is_none() => {
+                Ok(Destination::Email(email.trim().parse().with_context(|| format!("invalid email address '{}'", email.trim()))?))
+            }
+            (None, Some(_)) => anyhow::bail!("template only applies to webhook routes"),
+            _ => anyhow::bail!("exactly one of webhook_url or email is required"),
+        }
+    }
+}
+
+/// Quiet period between alerts for one target when `ALERT_COOLDOWN_SECS` is unset.
+const DEFAULT_COOLDOWN_SECS: u64 = 300;
+
+/// Sends alerts to the configured Slack incoming webhook, generic webhooks, and email, if any.
+#[derive(Clone)]
+pub struct Alerter {
+    client: reqwest::Client,
+    slack_webhook_url: Option<String>,
+    webhooks: Vec<WebhookTarget>,
+    email: Option<EmailSink>,
+    /// Quiet period after an alert for the same target (`ALERT_COOLDOWN_SECS`); zero disables it.
+    cooldown: Duration,
+    /// When each target was last alerted about by this process.
+    last_sent: Arc<Mutex<HashMap<i32, Instant>>>,
+}
+
+impl Alerter {
+    /// Reads the Slack webhook from the `SLACK_WEBHOOK_URL` secret and generic webhooks from
+    /// `ALERT_WEBHOOKS` (a JSON array of `{"url", "template"}`), and email from `SMTP_URL`, `ALERT_FROM`,
+    /// and `ALERT_TO` (comma-separated); alerting is disabled when none are set. `ALERT_COOLDOWN_SECS`
+    /// (default 300) sets the quiet period between alerts for one target.
+    pub fn from_env() -> anyhow::Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()?;
+        let slack_webhook_url = std::env::var("SLACK_WEBHOOK_URL")
+            .ok()
+            .filter(|url| !url.trim().is_empty());
+        let webhooks = match std::env::var("ALERT_WEBHOOKS") {
+            Ok(raw) if !raw.trim().is_empty() => parse_webhooks(&raw)?,
+            _ => Vec::new(),
+        };
+        let email = EmailSink::from_env()?;
+        if slack_webhook_url.is_none() && webhooks.is_empty() && email.is_none() {
+            info!("SLACK_WEBHOOK_URL, ALERT_WEBHOOKS, and SMTP_URL not set, status alerts are disabled");
+        }
+        let cooldown = Duration::from_secs(crate::env_or("ALERT_COOLDOWN_SECS", DEFAULT_COOLDOWN_SECS)?);
+        Ok(Self { client, slack_webhook_url, webhooks, email, cooldown, last_sent: Arc::default() })
+    }
+
+    /// Whether an alert for the target was sent within the cooldown. Recovery alerts ignore this.
+    pub fn in_cooldown(&self, target_id: i32) -> bool {
+        let last_sent = self.last_sent.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        last_sent.get(&target_id).is_some_and(|at| at.elapsed() < self.cooldown)
+    }
+
+    /// Whether a target that stays down is owed a reminder: the cooldown is enabled and has passed
+    /// since its last alert (or no alert was sent since startup).
+    pub fn reminder_due(&self, target_id: i32) -> bool {
+        !self.cooldown.is_zero() && !self.in_cooldown(target_id)
+    }
+
+    /// Whether email alerts are configured, which routes to email addresses rely on.
+    pub fn email_enabled(&self) -> bool {
+        self.email.is_some()
+    }
+
+    /// Starts the cooldown for the target; called for every alert that is sent.
+    pub fn start_cooldown(&self, target_id: i32) {
+        let mut last_sent = self.last_sent.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        last_sent.insert(target_id, Instant::now());
+    }
+
+    /// Delivers the alert to every sink concurrently, logging failures so one broken sink never
+    /// holds up the others, and reports which deliveries succeeded. Email is sent in the background
+    /// so a slow SMTP server never stalls the caller.
+    ///
+    /// With `routes`, only those destinations are alerted, emails going out as one message to
+    /// all routed addresses; without, the default Slack, webhook, and email sinks are.
+    pub async fn notify(&self, alert: &Alert<'_>, routes: &[Destination]) -> Deliveries {
+        let at = Utc::now();
+        let mut sent = Vec::new();
+        let routed_to: Vec<Mailbox> = routes
+            .iter()
+            .filter_map(|route| match route {
+                Destination::Email(to) => Some(to.clone()),
+                Destination::Webhook(_) => None,
+            })
+            .collect();
+        let email_sink = match &self.email {
+            Some(email) if routes.is_empty() => Some(email.clone()),
+            Some(email) if !routed_to.is_empty() => Some(EmailSink { to: routed_to, ..email.clone() }),
+            None if !routed_to.is_empty() => {
+                error!(target = %crate::log_host(alert.url), kind = ?alert.kind, sink = "email", "email route matched but email alerts are not configured");
+                sent.push(Delivery { channel: "email", ok: false });
+                None
+            }
+            _ => None,
+        };
+        let email = email_sink.clone().and_then(|email| send_email(email, alert, at));
+        if email_sink.is_some() && email.is_none() {
+            sent.push(Delivery { channel: "email", ok: false });
+        }
+        let slack = self
+            .slack_webhook_url
+            .iter()
+            .filter(|_| routes.is_empty())
+            .map(|url| ("slack", url.as_str(), json!({ "text": alert.summary() }).to_string()));
+        let routed_webhooks = routes.iter().filter_map(|route| match route {
+            Destination::Webhook(webhook) => Some(webhook),
+            Destination::Email(_) => None,
+        });
+        let webhooks: Vec<&WebhookTarget> =
+            if routes.is_empty() { self.webhooks.iter().collect() } else { routed_webhooks.collect() };
+        let webhooks = webhooks
+            .into_iter()
+            .map(|webhook| ("webhook", webhook.url.as_str(), webhook.render(alert, at)));
+
+        let results = join_all(slack.chain(webhooks).map(|(sink, url, body)| async move {
+            let result = self
+                .client
+                .post(url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(body)
+                .send()
+                .await
+                .and_then(|resp| resp.error_for_status());
+
+            let ok = result.is_ok();
+            match result {
+                Ok(_) => info!(target = %crate::log_host(alert.url), kind = ?alert.kind, sink, "alert sent"),
+                Err(e) => error!(target = %crate::log_host(alert.url), kind = ?alert.kind, sink, error = %e.without_url(), "failed to send alert"),
+            }
+            Delivery { channel: sink, ok }
+        }))
+        .await;
+        sent.extend(results);
+        Deliveries { sent, email }
+    }
+}
+
+/// Sends the email in a background task resolving to whether it was accepted; `None` when the
+/// message could not be built.
+fn send_email(email: EmailSink, alert: &Alert<'_>, at: DateTime<Utc>) -> Option<JoinHandle<bool>> {
+    let (target, kind) = (crate::log_host(alert.url), alert.kind);
+    let message = match email.message(alert, at) {
+        Ok(message) => message,
+        Err(e) => {
+            error!(%target, ?kind, sink = "email", error = %e, "failed to build alert email");
+            return None;
+        }
+    };
+    Some(tokio::spawn(async move {
+        match email.transport.send(message).await {
+            Ok(_) => {
+                info!(%target, ?kind, sink = "email", "alert sent");
+                true
+            }
+            Err(e) => {
+                error!(%target, ?kind, sink = "email", error = %e, "failed to send alert");
+                false
+            }
+        }
+    }))
+}
+
+/// Parses `ALERT_WEBHOOKS`, checking that each URL is valid and each template renders to JSON.
+fn parse_webhooks(raw: &str) -> anyhow::Result<Vec<WebhookTarget>> {
+    let webhooks: Vec<WebhookTarget> = serde_json::from_str(raw).context("ALERT_WEBHOOKS must be a JSON array of {\"url\", \"template\"}")?;
+    for webhook in &webhooks {
+        validate_webhook(webhook)?;
+    }
+    Ok(webhooks)
+}
+
+/// Checks that the webhook's URL is valid and its template renders to JSON.
+fn validate_webhook(webhook: &WebhookTarget) -> anyhow::Result<()> {
+    let metadata = crate::Metadata::new();
+    let sample = Alert { kind: AlertKind::Down, url: "https://example.com", status: Some(503), latency_ms: Some(120), metadata: &metadata };
+    reqwest::Url::parse(&webhook.url).with_context(|| format!("invalid alert webhook url '{}'", webhook.url))?;
+    serde_json::from_str::<serde_json::Value>(&webhook.render(&sample, Utc::now()))
+        .with_context(|| format!("template for webhook '{}' does not render to valid JSON", webhook.url))?;
+    Ok(())
+}
@@ -0,0 +1,126 @@
+//! API key authentication for the mutating endpoints, and signature checks for inbound webhooks.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use tracing::warn;
+
+/// Header clients use to present the API key.
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// The expected API key, read from the `API_KEY` secret.
+#[derive(Clone)]
+pub struct ApiKey(Option<Arc<str>>);
+
+impl ApiKey {
+    /// Reads the key from `API_KEY`; when unset every write request is rejected.
+    pub fn from_env() -> Self {
+        let key = std::env::var("API_KEY").ok().filter(|key| !key.trim().is_empty());
+        if key.is_none() {
+            warn!("API_KEY not set, all write requests will be rejected");
+        }
+        Self(key.map(Arc::from))
+    }
+
+    fn matches(&self, candidate: &[u8]) -> bool {
+        self.0
+            .as_deref()
+            .is_some_and(|key| constant_time_eq(key.as_bytes(), candidate))
+    }
+}
+
+/// Middleware rejecting requests without a valid `X-Api-Key` header: `401` when missing, `403` when wrong.
+pub async fn require_api_key(State(api_key): State<ApiKey>, req: Request, next: Next) -> Response {
+    let Some(provided) = req.headers().get(API_KEY_HEADER) else {
+        return (StatusCode::UNAUTHORIZED, "missing X-Api-Key header").into_response();
+    };
+    if !api_key.matches(provided.as_bytes()) {
+        warn!(method = %req.method(), path = %req.uri().path(), "rejected request with invalid API key");
+        return (StatusCode::FORBIDDEN, "invalid API key").into_response();
+    }
+    next.run(req).await
+}
+
+/// Header carrying the hex HMAC-SHA256 of an inbound webhook's body, optionally prefixed `sha256=`.
+pub const SIGNATURE_HEADER: &str = "x-signature";
+
+/// Shared secret for signed deploy notifications, read from the `DEPLOY_WEBHOOK_SECRET` secret.
+#[derive(Clone)]
+pub struct WebhookSecret(Option<Arc<[u8]>>);
+
+impl WebhookSecret {
+    /// Reads the secret from `DEPLOY_WEBHOOK_SECRET`; when unset every signature is rejected.
+    pub fn from_env() -> Self {
+        let secret = std::env::var("DEPLOY_WEBHOOK_SECRET").ok().filter(|secret| !secret.trim().is_empty());
+        if secret.is_none() {
+            warn!("DEPLOY_WEBHOOK_SECRET not set, all deploy notifications will be rejected");
+        }
+        Self(secret.map(|secret| Arc::from(secret.as_bytes())))
+    }
+
+    /// Whether `signature` is the HMAC-SHA256 of `body` under the secret. The comparison is
+    /// constant-time.
+    pub fn verify(&self, body: &[u8], signature: &str) -> bool {
+        let Some(secret) = self.0.as_deref() else {
+            return false;
+        };
+        let signature = signature.trim();
+        let Ok(expected) = hex::decode(signature.strip_prefix("sha256=").unwrap_or(signature)) else {
+            return false;
+        };
+        let Ok(mut mac) = Hmac::<Sha256>::new_from_slice(secret) else {
+            return false;
+        };
+        mac.update(body);
+        mac.verify_slice(&expected).is_ok()
+    }
+}
+
+/// Compares without short-circuiting on the first differing byte, so timing doesn't leak the key.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sign(secret: &[u8], body: &[u8]) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret).unwrap();
+        mac.update(body);
+        hex::encode(mac.finalize().into_bytes())
+    }
+
+    fn secret(key: &str) -> WebhookSecret {
+        WebhookSecret(Some(Arc::from(key.as_bytes())))
+    }
+
+    #[test]
+    fn verify_accepts_signatures_with_or_without_prefix() {
+        let signature = sign(b"s3cret", b"{\"sha\":\"abc\"}");
+        assert!(secret("s3cret").verify(b"{\"sha\":\"abc\"}", &signature));
+        assert!(secret("s3cret").verify(b"{\"sha\":\"abc\"}", &format!(" sha256={signature} ")));
+    }
+
+    #[test]
+    fn verify_rejects_wrong_signatures() {
+        let signature = sign(b"s3cret", b"body");
+        assert!(!secret("other").verify(b"body", &signature));
+        assert!(!secret("s3cret").verify(b"tampered", &signature));
+        assert!(!secret("s3cret").verify(b"body", "not hex"));
+        assert!(!secret("s3cret").verify(b"body", &signature[..32]));
+    }
+
+    #[test]
+    fn verify_rejects_everything_without_a_secret() {
+        let signature = sign(b"", b"body");
+        assert!(!WebhookSecret(None).verify(b"body", &signature));
+    }
+}
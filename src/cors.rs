@@ -0,0 +1,90 @@
+//! CORS policy for browser clients such as the dashboard frontend.
+
+use anyhow::Context;
+use axum::http::{HeaderName, HeaderValue, Method};
+use tower_http::cors::{AllowHeaders, AllowMethods, AllowOrigin, CorsLayer};
+use tracing::{info, warn};
+
+/// Builds the CORS layer from the environment:
+///
+/// - `ALLOWED_ORIGINS`: comma-separated origins such as `https://monitor.vercel.app`; any origin
+///   is allowed when unset.
+/// - `CORS_ALLOWED_METHODS` / `CORS_ALLOWED_HEADERS`: comma-separated lists; any method or header
+///   is allowed when unset.
+/// - `CORS_ALLOW_CREDENTIALS`: `true` to allow cookies and auth headers; requires `ALLOWED_ORIGINS`.
+pub fn layer_from_env() -> anyhow::Result<CorsLayer> {
+    let origins = list_var("ALLOWED_ORIGINS");
+    let methods = list_var("CORS_ALLOWED_METHODS");
+    let headers = list_var("CORS_ALLOWED_HEADERS");
+    let credentials: bool = crate::env_or("CORS_ALLOW_CREDENTIALS", false)?;
+
+    let allow_origin = match &origins {
+        Some(origins) => {
+            let origins = origins.iter().map(|origin| parse_origin(origin)).collect::<anyhow::Result<Vec<_>>>()?;
+            info!(origins = ?origins, "CORS restricted to allowed origins");
+            AllowOrigin::list(origins)
+        }
+        None if credentials => anyhow::bail!("CORS_ALLOW_CREDENTIALS requires ALLOWED_ORIGINS to be set"),
+        None => {
+            warn!("ALLOWED_ORIGINS not set, allowing cross-origin requests from any origin");
+            AllowOrigin::any()
+        }
+    };
+
+    // Browsers reject wildcards on credentialed requests, so echo the request back instead
+    let allow_methods = match methods {
+        Some(methods) => AllowMethods::list(
+            methods
+                .iter()
+                .map(|method| {
+                    Method::from_bytes(method.to_ascii_uppercase().as_bytes())
+                        .with_context(|| format!("invalid method '{method}' in CORS_ALLOWED_METHODS"))
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?,
+        ),
+        None if credentials => AllowMethods::mirror_request(),
+        None => AllowMethods::any(),
+    };
+    let allow_headers = match headers {
+        Some(headers) => AllowHeaders::list(
+            headers
+                .iter()
+                .map(|header| {
+                    HeaderName::from_bytes(header.as_bytes())
+                        .with_context(|| format!("invalid header '{header}' in CORS_ALLOWED_HEADERS"))
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?,
+        ),
+        None if credentials => AllowHeaders::mirror_request(),
+        None => AllowHeaders::any(),
+    };
+
+    Ok(CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods(allow_methods)
+        .allow_headers(allow_headers)
+        .expose_headers([crate::REQUEST_ID_HEADER])
+        .allow_credentials(credentials))
+}
+
+/// Splits a comma-separated variable into trimmed, non-empty entries; `None` when unset or blank.
+fn list_var(key: &str) -> Option<Vec<String>> {
+    let raw = std::env::var(key).ok()?;
+    let items: Vec<String> = raw
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect();
+    (!items.is_empty()).then_some(items)
+}
+
+/// Origins are compared exactly against the browser's `Origin` header, which never has a path.
+fn parse_origin(raw: &str) -> anyhow::Result<HeaderValue> {
+    let origin = raw.trim_end_matches('/');
+    anyhow::ensure!(
+        origin.starts_with("http://") || origin.starts_with("https://"),
+        "invalid origin '{raw}' in ALLOWED_ORIGINS: expected scheme://host[:port] (leave ALLOWED_ORIGINS unset to allow any origin)"
+    );
+    HeaderValue::from_str(origin).with_context(|| format!("invalid origin '{raw}' in ALLOWED_ORIGINS"))
+}
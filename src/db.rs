@@ -0,0 +1,59 @@
+//! Database connection pools. The API and the background workers draw from separate pools, so a
+//! burst of checks can't starve request handlers of connections (or the other way round).
+
+use std::time::Duration;
+
+use anyhow::Context;
+use sqlx::{postgres::PgPoolOptions, PgPool};
+
+/// Pool for request handlers and startup work.
+const DEFAULT_MAX_CONNECTIONS: u32 = 10;
+
+/// Pool for the check and retention workers; kept small since each check only holds a connection
+/// while writing its result.
+const DEFAULT_WORKER_MAX_CONNECTIONS: u32 = 4;
+
+/// How long to wait for a free connection before giving up.
+const DEFAULT_ACQUIRE_TIMEOUT_SECS: u64 = 5;
+
+pub struct Pools {
+    pub api: PgPool,
+    pub worker: PgPool,
+}
+
+impl Pools {
+    /// Connects both pools to `database_url`, sized from the environment:
+    ///
+    /// - `DB_MAX_CONNECTIONS`: connections for the API (default 10).
+    /// - `DB_WORKER_MAX_CONNECTIONS`: connections for the background workers (default 4).
+    /// - `DB_ACQUIRE_TIMEOUT_SECS`: wait for a free connection before failing with
+    ///   [`sqlx::Error::PoolTimedOut`] (default 5).
+    pub async fn connect_from_env(database_url: &str) -> anyhow::Result<Self> {
+        let max_connections = crate::env_or("DB_MAX_CONNECTIONS", DEFAULT_MAX_CONNECTIONS)?;
+        anyhow::ensure!(max_connections > 0, "DB_MAX_CONNECTIONS must be greater than zero");
+        let worker_max_connections = crate::env_or("DB_WORKER_MAX_CONNECTIONS", DEFAULT_WORKER_MAX_CONNECTIONS)?;
+        anyhow::ensure!(worker_max_connections > 0, "DB_WORKER_MAX_CONNECTIONS must be greater than zero");
+        let acquire_timeout_secs = crate::env_or("DB_ACQUIRE_TIMEOUT_SECS", DEFAULT_ACQUIRE_TIMEOUT_SECS)?;
+        anyhow::ensure!(acquire_timeout_secs > 0, "DB_ACQUIRE_TIMEOUT_SECS must be greater than zero");
+        let acquire_timeout = Duration::from_secs(acquire_timeout_secs);
+
+        let api = PgPoolOptions::new()
+            .max_connections(max_connections)
+            .acquire_timeout(acquire_timeout)
+            .connect(database_url)
+            .await
+            .context("failed to connect to the database")?;
+        let worker = PgPoolOptions::new()
+            .max_connections(worker_max_connections)
+            .acquire_timeout(acquire_timeout)
+            .connect(database_url)
+            .await
+            .context("failed to connect the worker pool to the database")?;
+        Ok(Pools { api, worker })
+    }
+
+    pub async fn close(&self) {
+        self.api.close().await;
+        self.worker.close().await;
+    }
+}
@@ -0,0 +1,98 @@
+//! GraphQL view of targets, their recent checks, and uptime, for clients that want one request
+//! per dashboard card. Resolvers run the same queries as the REST endpoints.
+
+use async_graphql::{
+    http::{playground_source, GraphQLPlaygroundConfig},
+    ComplexObject, Context, EmptyMutation, EmptySubscription, Error, Object, Result, Schema,
+};
+use axum::{extract::State, response::Html, routing::get, Json, Router};
+use sqlx::PgPool;
+use tracing::error;
+
+use crate::{Metadata, StatusRecord, Target, TimeoutAs, UptimeReport, DEFAULT_PAGE_LIMIT, MAX_PAGE_LIMIT};
+
+type MonitorSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+/// `POST /graphql` executes queries; `GET /graphql` serves the GraphQL Playground.
+pub fn routes<S>(pool: PgPool) -> Router<S> {
+    let schema = Schema::build(QueryRoot, EmptyMutation, EmptySubscription).data(pool).finish();
+    Router::new()
+        .route("/graphql", get(playground).post(execute))
+        .with_state(schema)
+}
+
+async fn execute(State(schema): State<MonitorSchema>, Json(request): Json<async_graphql::Request>) -> Json<async_graphql::Response> {
+    Json(schema.execute(request).await)
+}
+
+async fn playground() -> Html<String> {
+    Html(playground_source(GraphQLPlaygroundConfig::new("/graphql")))
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// Targets ordered by id, optionally only those carrying `tag`.
+    async fn targets(&self, ctx: &Context<'_>, tag: Option<String>) -> Result<Vec<Target>> {
+        crate::fetch_targets(pool(ctx), tag.as_deref(), None).await.map_err(db_error)
+    }
+
+    async fn target(&self, ctx: &Context<'_>, id: i32) -> Result<Option<Target>> {
+        crate::fetch_target(pool(ctx), id).await.map_err(db_error)
+    }
+
+    /// Share of healthy checks over a look-back `window` such as `30m`, `24h`, or `7d`.
+    async fn uptime(
+        &self,
+        ctx: &Context<'_>,
+        id: i32,
+        #[graphql(default_with = "String::from(\"24h\")")] window: String,
+        #[graphql(default)] exclude_maintenance: bool,
+        region: Option<String>,
+        #[graphql(default)] timeout_as: TimeoutAs,
+    ) -> Result<UptimeReport> {
+        let window_secs = crate::parse_window(&window).map_err(Error::new)?;
+        crate::fetch_uptime(pool(ctx), id, window_secs, exclude_maintenance, region.as_deref(), timeout_as)
+            .await
+            .map_err(db_error)
+    }
+}
+
+#[ComplexObject]
+impl Target {
+    /// Accepted status codes such as `200-299,301`; takes precedence over `expectedStatus`.
+    async fn expected_status_ranges(&self) -> Option<String> {
+        self.expected_status_ranges.as_ref().map(ToString::to_string)
+    }
+
+    /// Free-form key-value pairs such as an owner or runbook link.
+    async fn metadata(&self) -> async_graphql::Json<Metadata> {
+        async_graphql::Json(self.metadata.0.clone())
+    }
+
+    /// Most recent checks, newest first (`limit` defaults to 50, at most 500).
+    async fn checks(&self, ctx: &Context<'_>, limit: Option<i64>) -> Result<Vec<StatusRecord>> {
+        let limit = limit.unwrap_or(DEFAULT_PAGE_LIMIT);
+        if !(1..=MAX_PAGE_LIMIT).contains(&limit) {
+            return Err(Error::new(format!("limit must be between 1 and {MAX_PAGE_LIMIT}")));
+        }
+        let rows = crate::fetch_history(pool(ctx), self.id, limit, 0, None, None, None)
+            .await
+            .map_err(db_error)?;
+        Ok(rows.into_iter().map(StatusRecord::from).collect())
+    }
+}
+
+fn pool<'a>(ctx: &Context<'a>) -> &'a PgPool {
+    ctx.data_unchecked::<PgPool>()
+}
+
+/// Logs the query error and returns a generic message, like the REST handlers.
+fn db_error(e: sqlx::Error) -> Error {
+    error!(error = %e, "GraphQL query failed");
+    match e {
+        sqlx::Error::PoolTimedOut => Error::new("database busy: no connection available, try again shortly"),
+        _ => Error::new("DB error"),
+    }
+}
@@ -0,0 +1,6063 @@
+//! DevOps health monitor: an Axum API over Postgres plus a background worker that checks targets.
+
+mod alerts;
+mod auth;
+mod cors;
+mod db;
+mod graphql;
+mod openapi;
+mod ping;
+mod rate_limit;
+mod sequence;
+mod status_ranges;
+mod targets_file;
+mod tls;
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    convert::Infallible,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    pin::Pin,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Instant,
+};
+
+use anyhow::Context;
+use axum::{
+    extract::{
+        ws::{close_code, CloseFrame, Message, WebSocket, WebSocketUpgrade},
+        Path, Query, State,
+    },
+    http::{header, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    middleware,
+    routing::{delete, get, patch, post},
+    Json, Router,
+};
+use axum::body::Bytes;
+use chrono::{DateTime, Utc};
+use futures::{stream::{self, StreamExt}, SinkExt};
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
+use rand::{distributions::{Distribution, Uniform}, rngs::StdRng, SeedableRng};
+use regex::Regex;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::{types::Json as SqlJson, FromRow, PgPool, Postgres, QueryBuilder};
+use async_compression::tokio::bufread::{BrotliDecoder, GzipDecoder, ZlibDecoder};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt},
+    net::{TcpListener, TcpStream},
+    sync::{
+        broadcast::{self, error::RecvError},
+        Semaphore,
+    },
+    task::JoinHandle,
+    time::{sleep, Duration},
+};
+use tokio_util::{io::StreamReader, sync::CancellationToken};
+use tracing::{debug, error, field, info, info_span, instrument, warn, Span};
+use utoipa::{IntoParams, ToSchema};
+use tower_http::{
+    compression::{
+        predicate::{DefaultPredicate, Predicate, SizeAbove},
+        CompressionLayer,
+    },
+    request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer},
+    trace::TraceLayer,
+};
+use uuid::Uuid;
+
+use crate::alerts::{Alert, AlertKind, Alerter, Deliveries, Destination};
+use crate::auth::{require_api_key, ApiKey, WebhookSecret, SIGNATURE_HEADER};
+use crate::rate_limit::{rate_limit, RateLimiter};
+use crate::sequence::SequenceStep;
+use crate::status_ranges::StatusRanges;
+
+// Data models for API responses
+#[derive(Serialize, FromRow, Clone, ToSchema, async_graphql::SimpleObject)]
+#[graphql(complex)]
+pub struct Target {
+    id: i32,
+    url: String,
+    check_interval_secs: Option<i32>,
+    timeout_ms: Option<i32>,
+    expected_status: Option<i32>,
+    /// Accepted status codes such as `200-299,301`; takes precedence over `expected_status`.
+    #[schema(value_type = Option<String>)]
+    #[graphql(skip)]
+    expected_status_ranges: Option<StatusRanges>,
+    #[sqlx(try_from = "String")]
+    check_type: CheckType,
+    /// Extra request headers; never serialized since they often carry credentials.
+    #[serde(skip_serializing)]
+    #[graphql(skip)]
+    headers: Option<SqlJson<HashMap<String, String>>>,
+    follow_redirects: bool,
+    expected_body_substring: Option<String>,
+    /// Expiry of the TLS certificate seen during the latest check (HTTPS targets only).
+    cert_expires_at: Option<DateTime<Utc>>,
+    /// Request method for HTTP checks, stored upper-case (default `GET`).
+    http_method: String,
+    /// Body sent with the check request (methods other than `GET`/`HEAD` only).
+    request_body: Option<String>,
+    /// Paused targets keep their history but are not checked.
+    enabled: bool,
+    /// Free-form labels such as a team or environment, used to filter and group targets.
+    tags: Vec<String>,
+    /// Failed checks in a row, reset by the next healthy check.
+    consecutive_failures: i32,
+    /// Pattern the response body must match, validated when the target is saved.
+    expected_body_regex: Option<String>,
+    /// Media type the response `Content-Type` must start with, e.g. `application/json`.
+    expected_content_type: Option<String>,
+    /// `Content-Encoding` the response must carry, e.g. `gzip`, to catch a server that stopped compressing.
+    expected_content_encoding: Option<String>,
+    /// Response headers that must be present, by lower-case name, with the exact value unless it is empty.
+    #[schema(value_type = Option<Object>)]
+    #[graphql(skip)]
+    expected_headers: Option<SqlJson<HashMap<String, String>>>,
+    /// HTTP basic auth sent with each check to the target's own origin.
+    basic_auth_user: Option<String>,
+    /// Never serialized or logged.
+    #[serde(skip_serializing)]
+    #[graphql(skip)]
+    basic_auth_pass: Option<String>,
+    /// Healthy checks slower than this raise a slow alert.
+    latency_threshold_ms: Option<i32>,
+    /// Name shown on the public status page instead of the host.
+    display_name: Option<String>,
+    /// Only the newest this many checks are kept; `None` keeps all of them until `RETENTION_DAYS`.
+    max_history: Option<i32>,
+    /// Free-form key-value pairs such as an owner or runbook link, included in alerts.
+    #[schema(value_type = Object)]
+    #[graphql(skip)]
+    metadata: SqlJson<Metadata>,
+    /// Accept invalid TLS certificates, e.g. self-signed ones on internal services.
+    insecure_skip_tls_verify: bool,
+    /// Requests run in order by sequence checks; never serialized since they may carry credentials.
+    #[serde(skip_serializing)]
+    #[schema(value_type = Option<Vec<Object>>)]
+    #[graphql(skip)]
+    steps: Option<SqlJson<Vec<SequenceStep>>>,
+    /// Cron expression (UTC, with a seconds field) the target is checked on instead of its interval.
+    cron_schedule: Option<String>,
+    /// Address family checks connect over when the host has both A and AAAA records.
+    #[sqlx(try_from = "String")]
+    ip_family: IpFamily,
+}
+
+/// Schema-less target metadata: any JSON object.
+pub type Metadata = serde_json::Map<String, serde_json::Value>;
+
+impl Target {
+    /// How the target is identified in logs: the host only, never a URL that may embed credentials.
+    fn log_host(&self) -> String {
+        log_host(&self.url)
+    }
+
+    /// The host the target's checks connect to, without the port TCP targets carry.
+    fn host(&self) -> String {
+        match self.check_type {
+            CheckType::Tcp => self.url.rsplit_once(':').map_or(self.url.as_str(), |(host, _)| host).to_string(),
+            _ => self.log_host(),
+        }
+    }
+}
+
+/// The host of an HTTP URL, or the address itself for TCP and ping targets.
+fn log_host(url: &str) -> String {
+    reqwest::Url::parse(url)
+        .ok()
+        .and_then(|parsed| parsed.host_str().map(str::to_string))
+        .unwrap_or_else(|| url.to_string())
+}
+
+/// Correlates an API request or a check's outbound request with its log lines.
+pub(crate) const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Column list matching the fields of [`Target`], shared by every query that loads targets.
+const TARGET_COLUMNS: &str = "id, url, check_interval_secs, timeout_ms, expected_status, expected_status_ranges, check_type, headers, follow_redirects, expected_body_substring, cert_expires_at, http_method, request_body, enabled, tags, consecutive_failures, expected_body_regex, expected_content_type, basic_auth_user, basic_auth_pass, latency_threshold_ms, display_name, max_history, metadata, insecure_skip_tls_verify, steps, expected_headers, cron_schedule, ip_family, expected_content_encoding";
+
+/// Joins `status_since` for the target aliased `t`: the first check of its current healthy/unhealthy
+/// run, found by comparing each check with the one before it. The first check has nothing before it,
+/// so a target that has never changed state yields its first check.
+const STATUS_SINCE_LATERAL: &str = r#"
+        LEFT JOIN LATERAL (
+            SELECT MAX(checked_at) AS status_since
+            FROM (
+                SELECT checked_at, healthy, LAG(healthy) OVER (ORDER BY checked_at) AS prev_healthy
+                FROM (
+                    SELECT
+                        h.checked_at,
+                        check_healthy(h, t) AS healthy
+                    FROM health_checks h
+                    WHERE h.target_id = t.id
+                ) checks
+            ) runs
+            WHERE prev_healthy IS DISTINCT FROM healthy
+        ) run ON true"#;
+
+/// How a target is probed. HTTP targets store a URL, TCP targets `host:port`, and ping targets a
+/// bare host name or IP address. Sequence targets store the URL their steps are relative to.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, ToSchema, async_graphql::Enum)]
+#[serde(rename_all = "lowercase")]
+enum CheckType {
+    #[default]
+    Http,
+    Tcp,
+    Ping,
+    Sequence,
+}
+
+impl CheckType {
+    fn as_str(self) -> &'static str {
+        match self {
+            CheckType::Http => "http",
+            CheckType::Tcp => "tcp",
+            CheckType::Ping => "ping",
+            CheckType::Sequence => "sequence",
+        }
+    }
+}
+
+impl TryFrom<String> for CheckType {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "http" => Ok(CheckType::Http),
+            "tcp" => Ok(CheckType::Tcp),
+            "ping" => Ok(CheckType::Ping),
+            "sequence" => Ok(CheckType::Sequence),
+            other => Err(format!("unknown check type '{other}'")),
+        }
+    }
+}
+
+/// Which of a host's addresses checks may connect to. `Auto` leaves the choice to the resolver
+/// order (and happy eyeballs for HTTP); the others skip addresses of the other family.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, ToSchema, async_graphql::Enum)]
+#[serde(rename_all = "lowercase")]
+enum IpFamily {
+    #[default]
+    Auto,
+    V4,
+    V6,
+}
+
+impl IpFamily {
+    fn as_str(self) -> &'static str {
+        match self {
+            IpFamily::Auto => "auto",
+            IpFamily::V4 => "v4",
+            IpFamily::V6 => "v6",
+        }
+    }
+
+    fn allows(self, ip: IpAddr) -> bool {
+        match self {
+            IpFamily::Auto => true,
+            IpFamily::V4 => ip.is_ipv4(),
+            IpFamily::V6 => ip.is_ipv6(),
+        }
+    }
+}
+
+impl TryFrom<String> for IpFamily {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "auto" => Ok(IpFamily::Auto),
+            "v4" => Ok(IpFamily::V4),
+            "v6" => Ok(IpFamily::V6),
+            other => Err(format!("unknown ip family '{other}'")),
+        }
+    }
+}
+
+#[derive(Serialize, FromRow, Clone, ToSchema, async_graphql::SimpleObject)]
+pub struct HealthCheckRecord {
+    id: i32,
+    target_id: i32,
+    checked_at: DateTime<Utc>,
+    status_code: Option<i32>,
+    response_time_ms: Option<i32>,
+    /// Time until the final response's headers arrived, excluding the body download (HTTP targets only).
+    ttfb_ms: Option<i32>,
+    /// Size of the drained response body (HTTP targets only).
+    content_length_bytes: Option<i64>,
+    redirect_count: Option<i32>,
+    body_match: Option<bool>,
+    /// Time spent resolving the target's host name (HTTP targets only).
+    dns_ms: Option<i32>,
+    /// Whether the response `Content-Type` matched `expected_content_type` (only when one is configured).
+    content_type_match: Option<bool>,
+    /// Whether the response carried every `expected_headers` entry (only when some are configured).
+    headers_match: Option<bool>,
+    /// `Content-Encoding` of the final response as sent, e.g. `gzip`, before it was decoded (HTTP targets only).
+    content_encoding: Option<String>,
+    /// Whether `content_encoding` matched `expected_content_encoding` (only when one is configured).
+    encoding_match: Option<bool>,
+    /// `REGION` of the worker that performed the check.
+    region: String,
+    /// Protocol negotiated for the final response, e.g. `HTTP/2` (HTTP targets only).
+    http_version: Option<String>,
+    /// Why the check failed without a status: `timeout`, `dns`, `connect`, `tls`, `body`, `decode`,
+    /// `redirect`, `ping`, `request`, or `body_too_large`, plus `unexpected_status`, `capture`, or
+    /// `config` for sequences; `None` when the target responded.
+    error_kind: Option<String>,
+    /// 0-based index of the step that failed a sequence check.
+    failed_step: Option<i32>,
+    /// Address the check connected to, telling IPv4 from IPv6 problems apart; `None` when no
+    /// connection was made.
+    resolved_ip: Option<String>,
+}
+
+/// A health check annotated with whether it met its target's expected status.
+#[derive(Serialize, ToSchema, async_graphql::SimpleObject)]
+struct StatusRecord {
+    #[serde(flatten)]
+    #[graphql(flatten)]
+    record: HealthCheckRecord,
+    healthy: bool,
+}
+
+/// Message pushed to `/api/live` and `/api/events` subscribers for every recorded check.
+#[derive(Serialize)]
+struct LiveCheck<'a> {
+    url: &'a str,
+    #[serde(flatten)]
+    check: StatusRecord,
+}
+
+/// A serialized [`LiveCheck`], tagged with its target so subscribers can filter without parsing it.
+#[derive(Clone)]
+struct LiveUpdate {
+    target_id: i32,
+    message: String,
+}
+
+#[derive(FromRow)]
+struct StatusRow {
+    #[sqlx(flatten)]
+    record: HealthCheckRecord,
+    expected_status: Option<i32>,
+    expected_status_ranges: Option<StatusRanges>,
+}
+
+impl From<StatusRow> for StatusRecord {
+    fn from(row: StatusRow) -> Self {
+        let record = row.record;
+        StatusRecord {
+            healthy: is_check_healthy(
+                record.status_code,
+                row.expected_status,
+                row.expected_status_ranges.as_ref(),
+                &[record.body_match, record.content_type_match, record.headers_match, record.encoding_match],
+            ),
+            record,
+        }
+    }
+}
+
+/// Latest check of each requested target, keyed by id; `None` for targets not checked yet.
+#[derive(Serialize)]
+struct BatchStatus {
+    statuses: BTreeMap<i32, Option<StatusRecord>>,
+    /// Requested ids with no such target.
+    missing: Vec<i32>,
+}
+
+/// One page of a target's check history, newest first.
+#[derive(Serialize, ToSchema)]
+struct StatusPage {
+    records: Vec<StatusRecord>,
+    total: i64,
+    limit: i64,
+    offset: i64,
+    has_more: bool,
+}
+
+#[derive(Serialize, async_graphql::SimpleObject)]
+struct UptimeReport {
+    target_id: i32,
+    window_secs: i64,
+    checks: i64,
+    up: i64,
+    /// Timed-out checks, when counted separately with `timeout_as=degraded`; otherwise always 0.
+    degraded: i64,
+    /// Failed checks not counted as `degraded`.
+    down: i64,
+    /// `None` when there were no checks in the window.
+    uptime_pct: Option<f64>,
+}
+
+/// Combined uptime of the targets carrying a tag.
+#[derive(Serialize)]
+struct TagUptimeReport {
+    tag: String,
+    window_secs: i64,
+    mode: UptimeMode,
+    /// Targets with at least one check in the window; the others don't count towards the result.
+    targets: usize,
+    checks: i64,
+    up: i64,
+    /// `None` when no target had checks in the window.
+    uptime_pct: Option<f64>,
+    /// The target with the lowest uptime, the lower id on a tie.
+    worst_target_id: Option<i32>,
+    worst_uptime_pct: Option<f64>,
+}
+
+/// How `/api/uptime` combines the uptime of several targets.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum UptimeMode {
+    /// Healthy checks over all checks, so frequently checked targets weigh more.
+    #[default]
+    Pooled,
+    /// The mean of each target's own uptime, weighing every target equally.
+    Average,
+}
+
+/// How `/api/status/:target_id/uptime` counts checks that timed out.
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, async_graphql::Enum)]
+#[serde(rename_all = "lowercase")]
+enum TimeoutAs {
+    /// A slow-but-alive target, counted apart from hard failures.
+    Degraded,
+    #[default]
+    Down,
+}
+
+/// A run of consecutive failed checks.
+#[derive(Serialize)]
+struct Incident {
+    started_at: DateTime<Utc>,
+    /// Time of the first healthy check after the run; `None` while the target is still down.
+    ended_at: Option<DateTime<Utc>>,
+    /// Length of the incident so far for unresolved incidents.
+    duration_secs: i64,
+    resolved: bool,
+    /// `error_kind` of the check that started the incident; `None` when the target responded,
+    /// e.g. with an unexpected status.
+    error_kind: Option<String>,
+}
+
+#[derive(FromRow)]
+struct IncidentRow {
+    started_at: DateTime<Utc>,
+    ended_at: Option<DateTime<Utc>>,
+    error_kind: Option<String>,
+}
+
+impl From<IncidentRow> for Incident {
+    fn from(row: IncidentRow) -> Self {
+        let end = row.ended_at.unwrap_or_else(Utc::now);
+        Incident {
+            started_at: row.started_at,
+            ended_at: row.ended_at,
+            duration_secs: (end - row.started_at).num_seconds(),
+            resolved: row.ended_at.is_some(),
+            error_kind: row.error_kind,
+        }
+    }
+}
+
+/// Latency percentiles over successful checks; all `None` when there were no samples.
+#[derive(Serialize, FromRow)]
+struct LatencyReport {
+    target_id: i32,
+    window_secs: i64,
+    samples: i64,
+    p50: Option<f64>,
+    p90: Option<f64>,
+    p95: Option<f64>,
+    p99: Option<f64>,
+}
+
+/// How many checks in the window returned each status code.
+#[derive(Serialize)]
+struct StatusCodeReport {
+    target_id: i32,
+    window_secs: i64,
+    total: i64,
+    /// Counts keyed by status code, with checks that got no response under `error`.
+    codes: BTreeMap<String, i64>,
+}
+
+/// Checks slower than `mean_ms + k * stddev_ms` over the window, newest first.
+#[derive(Serialize)]
+struct AnomalyReport {
+    target_id: i32,
+    window_secs: i64,
+    k: f64,
+    /// Checks with a response time in the window.
+    samples: i64,
+    /// `None` when there were no samples.
+    mean_ms: Option<f64>,
+    /// Population standard deviation; `None` when there were no samples.
+    stddev_ms: Option<f64>,
+    anomalies: Vec<Anomaly>,
+}
+
+#[derive(Serialize, FromRow)]
+struct Anomaly {
+    id: i32,
+    checked_at: DateTime<Utc>,
+    status_code: Option<i32>,
+    response_time_ms: i32,
+    /// Standard deviations above the window's mean.
+    z_score: f64,
+}
+
+/// Health of all active targets rolled into one status, for a summary badge.
+#[derive(Serialize)]
+struct OverallStatus {
+    /// `maintenance` while the service is in maintenance mode; otherwise `healthy` when no target
+    /// is unhealthy, `down` when all are, `degraded` otherwise.
+    status: &'static str,
+    total: i64,
+    healthy: i64,
+    unhealthy: i64,
+    /// Most recent healthy/unhealthy transition of any target; `None` when nothing has been checked.
+    status_since: Option<DateTime<Utc>>,
+    status_duration_secs: Option<i64>,
+}
+
+impl OverallStatus {
+    fn new(total: i64, healthy: i64, status_since: Option<DateTime<Utc>>, maintenance: bool) -> Self {
+        let unhealthy = total - healthy;
+        let status = if maintenance {
+            "maintenance"
+        } else if unhealthy == 0 {
+            "healthy"
+        } else if healthy == 0 {
+            "down"
+        } else {
+            "degraded"
+        };
+        OverallStatus {
+            status,
+            total,
+            healthy,
+            unhealthy,
+            status_since,
+            status_duration_secs: status_duration_secs(status_since),
+        }
+    }
+}
+
+/// Service-wide maintenance mode, as switched by `POST /api/maintenance/on` and `/off`.
+#[derive(Serialize, FromRow)]
+struct ServiceMaintenance {
+    maintenance: bool,
+    /// When maintenance mode was switched on; `None` while it is off.
+    maintenance_since: Option<DateTime<Utc>>,
+}
+
+/// Status page feed safe to publish: display names and coarse states only, no URLs, ids, or latencies.
+#[derive(Serialize)]
+struct PublicStatus {
+    /// `outage` when every listed target is down, `degraded` when any is not operational,
+    /// `operational` otherwise.
+    status: &'static str,
+    targets: Vec<PublicTargetStatus>,
+}
+
+#[derive(Serialize)]
+struct PublicTargetStatus {
+    name: String,
+    /// `operational`, `degraded` when up but slower than `latency_threshold_ms`, or `outage`.
+    status: &'static str,
+}
+
+#[derive(FromRow)]
+struct PublicStatusRow {
+    url: String,
+    display_name: Option<String>,
+    healthy: bool,
+    slow: bool,
+}
+
+impl From<PublicStatusRow> for PublicTargetStatus {
+    fn from(row: PublicStatusRow) -> Self {
+        let status = match (row.healthy, row.slow) {
+            (false, _) => "outage",
+            (true, true) => "degraded",
+            (true, false) => "operational",
+        };
+        PublicTargetStatus {
+            name: row.display_name.unwrap_or_else(|| public_host(&row.url)),
+            status,
+        }
+    }
+}
+
+impl PublicStatus {
+    fn new(targets: Vec<PublicTargetStatus>) -> Self {
+        let status = if !targets.is_empty() && targets.iter().all(|t| t.status == "outage") {
+            "outage"
+        } else if targets.iter().any(|t| t.status != "operational") {
+            "degraded"
+        } else {
+            "operational"
+        };
+        PublicStatus { status, targets }
+    }
+}
+
+/// The bare host of a target, without scheme, credentials, port, or path.
+fn public_host(url: &str) -> String {
+    let host = log_host(url);
+    match host.rsplit_once(':') {
+        // A TCP `host:port` address; IPv6 hosts keep their colons
+        Some((name, port)) if !name.contains(':') && port.parse::<u16>().is_ok() => name.to_string(),
+        _ => host,
+    }
+}
+
+/// One timed `SELECT 1` against the monitor's own database.
+#[derive(Serialize, FromRow)]
+struct DbLatencySample {
+    checked_at: DateTime<Utc>,
+    latency_ms: f64,
+    region: String,
+}
+
+/// Body of `GET /api/worker/db-latency`.
+#[derive(Serialize)]
+struct DbLatencyReport {
+    window_secs: i64,
+    /// Oldest first.
+    samples: Vec<DbLatencySample>,
+}
+
+/// Checks in the window grouped into fixed-width buckets, oldest first.
+#[derive(Serialize)]
+struct Timeseries {
+    target_id: i32,
+    window_secs: i64,
+    bucket_secs: i64,
+    buckets: Vec<TimeseriesBucket>,
+}
+
+/// One bucket of a [`Timeseries`]; empty buckets are kept so charts get a continuous x-axis.
+#[derive(Serialize, FromRow)]
+struct TimeseriesBucket {
+    bucket_start: DateTime<Utc>,
+    checks: i64,
+    /// Mean response time of checks that got a response; `None` when there were none.
+    avg_latency_ms: Option<f64>,
+    /// `None` for buckets without checks.
+    failures: Option<i64>,
+}
+
+/// One tile of the dashboard status grid.
+#[derive(Serialize, Clone)]
+struct DashboardEntry {
+    id: i32,
+    url: String,
+    check_type: CheckType,
+    enabled: bool,
+    tags: Vec<String>,
+    consecutive_failures: i32,
+    last_checked_at: Option<DateTime<Utc>>,
+    last_status_code: Option<i32>,
+    last_response_time_ms: Option<i32>,
+    /// Exponential moving average of response times (`EMA_ALPHA`), smoothing out single slow samples.
+    latency_ema_ms: Option<f64>,
+    /// `None` until the target has been checked at least once.
+    healthy: Option<bool>,
+    /// The latest healthy check was slower than `latency_threshold_ms`.
+    slow: bool,
+    /// First check of the current healthy/unhealthy run, i.e. the last state change or, for a
+    /// target that has never changed state, its first check.
+    status_since: Option<DateTime<Utc>>,
+    status_duration_secs: Option<i64>,
+    checks_24h: i64,
+    uptime_pct_24h: Option<f64>,
+    /// Latest check from each region that reported in the last 24 hours.
+    regions: Vec<RegionStatus>,
+}
+
+/// Latest check of a target as seen from one region.
+#[derive(Serialize, FromRow, Clone)]
+struct RegionStatus {
+    #[serde(skip)]
+    target_id: i32,
+    region: String,
+    last_checked_at: DateTime<Utc>,
+    last_status_code: Option<i32>,
+    healthy: bool,
+}
+
+#[derive(FromRow)]
+struct DashboardRow {
+    id: i32,
+    url: String,
+    #[sqlx(try_from = "String")]
+    check_type: CheckType,
+    expected_status: Option<i32>,
+    expected_status_ranges: Option<StatusRanges>,
+    enabled: bool,
+    tags: Vec<String>,
+    consecutive_failures: i32,
+    last_checked_at: Option<DateTime<Utc>>,
+    last_status_code: Option<i32>,
+    last_response_time_ms: Option<i32>,
+    latency_ema_ms: Option<f64>,
+    slow: bool,
+    last_body_match: Option<bool>,
+    last_content_type_match: Option<bool>,
+    last_headers_match: Option<bool>,
+    last_encoding_match: Option<bool>,
+    status_since: Option<DateTime<Utc>>,
+    checks_24h: i64,
+    uptime_pct_24h: Option<f64>,
+}
+
+impl From<DashboardRow> for DashboardEntry {
+    fn from(row: DashboardRow) -> Self {
+        let healthy = row
+            .last_checked_at
+            .map(|_| {
+                is_check_healthy(
+                    row.last_status_code,
+                    row.expected_status,
+                    row.expected_status_ranges.as_ref(),
+                    &[row.last_body_match, row.last_content_type_match, row.last_headers_match, row.last_encoding_match],
+                )
+            });
+        DashboardEntry {
+            id: row.id,
+            url: row.url,
+            check_type: row.check_type,
+            enabled: row.enabled,
+            tags: row.tags,
+            consecutive_failures: row.consecutive_failures,
+            last_checked_at: row.last_checked_at,
+            last_status_code: row.last_status_code,
+            last_response_time_ms: row.last_response_time_ms,
+            latency_ema_ms: row.latency_ema_ms,
+            healthy,
+            slow: row.slow,
+            status_since: row.status_since,
+            status_duration_secs: status_duration_secs(row.status_since),
+            checks_24h: row.checks_24h,
+            uptime_pct_24h: row.uptime_pct_24h,
+            regions: Vec::new(),
+        }
+    }
+}
+
+/// Seconds elapsed since `since`, for "up for 3 days" style displays.
+fn status_duration_secs(since: Option<DateTime<Utc>>) -> Option<i64> {
+    since.map(|since| (Utc::now() - since).num_seconds().max(0))
+}
+
+#[derive(Serialize)]
+struct CertReport {
+    target_id: i32,
+    cert_expires_at: Option<DateTime<Utc>>,
+    /// Whole days until expiry; negative once the certificate has expired.
+    days_until_expiry: Option<i64>,
+}
+
+/// Error-budget burn over the short and long windows. A burn rate of 1 uses up the budget
+/// exactly over the SLO period; higher rates exhaust it that many times faster.
+#[derive(Serialize)]
+struct BurnRateReport {
+    target_id: i32,
+    slo_pct: f64,
+    short_window_secs: i64,
+    long_window_secs: i64,
+    /// `None` when there were no checks in the window.
+    short_burn_rate: Option<f64>,
+    long_burn_rate: Option<f64>,
+    /// Both windows are over their thresholds, which raises a burn-rate alert.
+    burning: bool,
+}
+
+#[derive(Serialize)]
+struct FlapReport {
+    target_id: i32,
+    /// Number of recent checks considered (at most `FLAP_WINDOW`).
+    checks: i64,
+    transitions: i64,
+    /// Share of consecutive check pairs that changed state, from 0.0 to 1.0.
+    flap_score: f64,
+    is_flapping: bool,
+}
+
+/// A period during which a target's downtime is expected and alerts are suppressed.
+#[derive(Serialize, FromRow)]
+struct MaintenanceWindow {
+    id: i32,
+    target_id: i32,
+    starts_at: DateTime<Utc>,
+    ends_at: DateTime<Utc>,
+}
+
+/// Sends alerts for targets carrying `tag` and/or whose `metadata` has this `severity` to a
+/// webhook or email address instead of the default sinks.
+#[derive(Serialize, FromRow)]
+struct AlertRoute {
+    id: i32,
+    tag: Option<String>,
+    /// Matched case-insensitively against the target's `metadata.severity` string.
+    severity: Option<String>,
+    webhook_url: Option<String>,
+    /// Webhook body template, as for `ALERT_WEBHOOKS`.
+    template: Option<String>,
+    email: Option<String>,
+    created_at: DateTime<Utc>,
+}
+
+/// One delivery of an alert to one sink.
+#[derive(Serialize, FromRow)]
+struct AlertRecord {
+    id: i64,
+    target_id: i32,
+    /// `down`, `up`, `slow`, `still_down`, or `burn_rate`.
+    kind: String,
+    /// `slack`, `webhook`, or `email`.
+    channel: String,
+    sent_at: DateTime<Utc>,
+    delivery_ok: bool,
+}
+
+/// A deploy of a target reported by CI.
+#[derive(Serialize, FromRow)]
+struct Deploy {
+    id: i64,
+    target_id: i32,
+    deployed_at: DateTime<Utc>,
+    /// The notification's JSON body, such as a version or commit, if it had one.
+    details: Option<SqlJson<serde_json::Value>>,
+}
+
+// Request payloads
+#[derive(Deserialize, ToSchema)]
+struct CreateTarget {
+    url: String,
+    #[serde(default)]
+    check_type: CheckType,
+    /// Seconds between checks; `CHECK_INTERVAL_SECS` when omitted.
+    check_interval_secs: Option<i32>,
+    timeout_ms: Option<i32>,
+    /// `200` when omitted.
+    expected_status: Option<i32>,
+    headers: Option<HashMap<String, String>>,
+    /// `true` when omitted.
+    follow_redirects: Option<bool>,
+    expected_body_substring: Option<String>,
+    http_method: Option<String>,
+    request_body: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    expected_body_regex: Option<String>,
+    expected_content_type: Option<String>,
+    /// `gzip`, `br`, or `deflate`.
+    expected_content_encoding: Option<String>,
+    /// Response headers to assert, by name; an empty value only requires the header.
+    expected_headers: Option<HashMap<String, String>>,
+    basic_auth_user: Option<String>,
+    basic_auth_pass: Option<String>,
+    latency_threshold_ms: Option<i32>,
+    display_name: Option<String>,
+    max_history: Option<i32>,
+    expected_status_ranges: Option<String>,
+    #[schema(value_type = Option<Object>)]
+    metadata: Option<Metadata>,
+    #[serde(default)]
+    insecure_skip_tls_verify: bool,
+    /// Required for sequence checks.
+    #[schema(value_type = Option<Vec<Object>>)]
+    steps: Option<Vec<SequenceStep>>,
+    /// Check on this cron schedule (UTC) instead of an interval, e.g. `*/5 9-17 * * Mon-Fri`.
+    cron_schedule: Option<String>,
+    #[serde(default)]
+    ip_family: IpFamily,
+}
+
+impl CreateTarget {
+    /// Checks every setting, normalizing the URL, method, tags, and assertions as they are stored.
+    fn validated(mut self) -> Result<Self, String> {
+        self.url = validate_address(self.check_type, &self.url)?;
+        validate_steps(self.check_type, self.steps.as_mut(), &self.url)?;
+        let method = validate_http_method(self.http_method.as_deref().unwrap_or("GET"), self.request_body.as_deref())?;
+        self.http_method = Some(method);
+        validate_basic_auth(self.basic_auth_user.as_deref(), self.basic_auth_pass.as_deref())?;
+        TargetFields {
+            check_interval_secs: self.check_interval_secs,
+            timeout_ms: self.timeout_ms,
+            expected_status: self.expected_status,
+            headers: self.headers.as_ref(),
+            latency_threshold_ms: self.latency_threshold_ms,
+            max_history: self.max_history,
+            tags: Some(&mut self.tags),
+            expected_body_regex: self.expected_body_regex.as_deref(),
+            expected_content_type: self.expected_content_type.as_mut(),
+            expected_content_encoding: self.expected_content_encoding.as_mut(),
+            expected_headers: self.expected_headers.as_mut(),
+            display_name: self.display_name.as_mut(),
+            expected_status_ranges: self.expected_status_ranges.as_mut(),
+            cron_schedule: self.cron_schedule.as_mut(),
+        }
+        .validate()?;
+        Ok(self)
+    }
+
+    /// The target these validated settings would create, with the column defaults for everything
+    /// else, for probing it without saving it.
+    fn draft(self) -> Target {
+        Target {
+            id: 0,
+            url: self.url,
+            check_interval_secs: self.check_interval_secs,
+            timeout_ms: self.timeout_ms,
+            expected_status: self.expected_status.or(Some(200)),
+            expected_status_ranges: self.expected_status_ranges.and_then(|ranges| ranges.parse().ok()),
+            check_type: self.check_type,
+            headers: self.headers.map(SqlJson),
+            follow_redirects: self.follow_redirects.unwrap_or(true),
+            expected_body_substring: self.expected_body_substring,
+            cert_expires_at: None,
+            http_method: self.http_method.unwrap_or_else(|| "GET".into()),
+            request_body: self.request_body,
+            enabled: true,
+            tags: self.tags,
+            consecutive_failures: 0,
+            expected_body_regex: self.expected_body_regex,
+            expected_content_type: self.expected_content_type,
+            expected_content_encoding: self.expected_content_encoding,
+            expected_headers: self.expected_headers.map(SqlJson),
+            basic_auth_user: self.basic_auth_user,
+            basic_auth_pass: self.basic_auth_pass,
+            latency_threshold_ms: self.latency_threshold_ms,
+            display_name: self.display_name,
+            max_history: self.max_history,
+            metadata: SqlJson(self.metadata.unwrap_or_default()),
+            insecure_skip_tls_verify: self.insecure_skip_tls_verify,
+            steps: self.steps.map(SqlJson),
+            cron_schedule: self.cron_schedule,
+            ip_family: self.ip_family,
+        }
+    }
+}
+
+/// What a dry-run check of an unsaved target observed.
+#[derive(Serialize)]
+struct TargetValidation {
+    /// Whether the check would be recorded as healthy.
+    healthy: bool,
+    status_code: Option<i32>,
+    response_time_ms: Option<i32>,
+    /// URL of the final response, after any redirects (HTTP targets only).
+    final_url: Option<String>,
+    /// The final response's `Content-Type` (HTTP targets only).
+    content_type: Option<String>,
+    /// Why the check got no response, as recorded in `health_checks.error_kind`.
+    error_kind: Option<&'static str>,
+    /// The failure in words, e.g. to show next to the URL field.
+    error: Option<String>,
+}
+
+/// A new alert route: at least one of `tag` and `severity` to match (a target must match both
+/// when both are set) and exactly one of `webhook_url` or `email` as its destination.
+#[derive(Deserialize, Debug)]
+struct CreateAlertRoute {
+    tag: Option<String>,
+    severity: Option<String>,
+    webhook_url: Option<String>,
+    template: Option<String>,
+    email: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct CreateMaintenanceWindow {
+    starts_at: DateTime<Utc>,
+    ends_at: DateTime<Utc>,
+}
+
+/// Partial update for a target. Absent fields are left unchanged; for nullable settings an
+/// explicit `null` resets the column.
+#[derive(Deserialize, ToSchema)]
+struct UpdateTarget {
+    url: Option<String>,
+    check_type: Option<CheckType>,
+    #[serde(default, deserialize_with = "double_option")]
+    check_interval_secs: Option<Option<i32>>,
+    #[serde(default, deserialize_with = "double_option")]
+    timeout_ms: Option<Option<i32>>,
+    #[serde(default, deserialize_with = "double_option")]
+    expected_status: Option<Option<i32>>,
+    #[serde(default, deserialize_with = "double_option")]
+    headers: Option<Option<HashMap<String, String>>>,
+    follow_redirects: Option<bool>,
+    #[serde(default, deserialize_with = "double_option")]
+    expected_body_substring: Option<Option<String>>,
+    http_method: Option<String>,
+    #[serde(default, deserialize_with = "double_option")]
+    request_body: Option<Option<String>>,
+    /// Replaces the target's tags; an empty list clears them.
+    tags: Option<Vec<String>>,
+    #[serde(default, deserialize_with = "double_option")]
+    expected_body_regex: Option<Option<String>>,
+    #[serde(default, deserialize_with = "double_option")]
+    expected_content_type: Option<Option<String>>,
+    #[serde(default, deserialize_with = "double_option")]
+    expected_content_encoding: Option<Option<String>>,
+    #[serde(default, deserialize_with = "double_option")]
+    expected_headers: Option<Option<HashMap<String, String>>>,
+    #[serde(default, deserialize_with = "double_option")]
+    basic_auth_user: Option<Option<String>>,
+    #[serde(default, deserialize_with = "double_option")]
+    basic_auth_pass: Option<Option<String>>,
+    #[serde(default, deserialize_with = "double_option")]
+    latency_threshold_ms: Option<Option<i32>>,
+    #[serde(default, deserialize_with = "double_option")]
+    display_name: Option<Option<String>>,
+    #[serde(default, deserialize_with = "double_option")]
+    max_history: Option<Option<i32>>,
+    #[serde(default, deserialize_with = "double_option")]
+    expected_status_ranges: Option<Option<String>>,
+    /// Replaces the target's metadata; `null` clears it.
+    #[serde(default, deserialize_with = "double_option")]
+    #[schema(value_type = Option<Object>)]
+    metadata: Option<Option<Metadata>>,
+    insecure_skip_tls_verify: Option<bool>,
+    /// Replaces a sequence target's steps.
+    #[serde(default, deserialize_with = "double_option")]
+    #[schema(value_type = Option<Vec<Object>>)]
+    steps: Option<Option<Vec<SequenceStep>>>,
+    #[serde(default, deserialize_with = "double_option")]
+    cron_schedule: Option<Option<String>>,
+    ip_family: Option<IpFamily>,
+}
+
+impl UpdateTarget {
+    fn is_empty(&self) -> bool {
+        self.url.is_none()
+            && self.check_type.is_none()
+            && self.check_interval_secs.is_none()
+            && self.timeout_ms.is_none()
+            && self.expected_status.is_none()
+            && self.headers.is_none()
+            && self.follow_redirects.is_none()
+            && self.expected_body_substring.is_none()
+            && self.http_method.is_none()
+            && self.request_body.is_none()
+            && self.tags.is_none()
+            && self.expected_body_regex.is_none()
+            && self.expected_content_type.is_none()
+            && self.expected_content_encoding.is_none()
+            && self.expected_headers.is_none()
+            && self.basic_auth_user.is_none()
+            && self.basic_auth_pass.is_none()
+            && self.latency_threshold_ms.is_none()
+            && self.display_name.is_none()
+            && self.max_history.is_none()
+            && self.expected_status_ranges.is_none()
+            && self.metadata.is_none()
+            && self.insecure_skip_tls_verify.is_none()
+            && self.steps.is_none()
+            && self.cron_schedule.is_none()
+            && self.ip_family.is_none()
+    }
+
+    /// The settings this update sets to a value, for checking them as on create.
+    fn fields(&mut self) -> TargetFields<'_> {
+        TargetFields {
+            check_interval_secs: self.check_interval_secs.flatten(),
+            timeout_ms: self.timeout_ms.flatten(),
+            expected_status: self.expected_status.flatten(),
+            headers: self.headers.as_ref().and_then(Option::as_ref),
+            latency_threshold_ms: self.latency_threshold_ms.flatten(),
+            max_history: self.max_history.flatten(),
+            tags: self.tags.as_mut(),
+            expected_body_regex: self.expected_body_regex.as_ref().and_then(Option::as_deref),
+            expected_content_type: self.expected_content_type.as_mut().and_then(Option::as_mut),
+            expected_content_encoding: self.expected_content_encoding.as_mut().and_then(Option::as_mut),
+            expected_headers: self.expected_headers.as_mut().and_then(Option::as_mut),
+            display_name: self.display_name.as_mut().and_then(Option::as_mut),
+            expected_status_ranges: self.expected_status_ranges.as_mut().and_then(Option::as_mut),
+            cron_schedule: self.cron_schedule.as_mut().and_then(Option::as_mut),
+        }
+    }
+}
+
+/// Distinguishes a field set to `null` (`Some(None)`) from one that is absent (`None`, via `default`).
+fn double_option<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// One target in a bulk import; every setting except `url` is optional.
+#[derive(Deserialize)]
+struct BulkTarget {
+    url: String,
+    /// Optional rather than defaulted so blank CSV cells fall back to HTTP too.
+    check_type: Option<CheckType>,
+    check_interval_secs: Option<i32>,
+    timeout_ms: Option<i32>,
+    expected_status: Option<i32>,
+    follow_redirects: Option<bool>,
+    expected_body_substring: Option<String>,
+    http_method: Option<String>,
+    request_body: Option<String>,
+    /// JSON bodies only; CSV has no column for headers.
+    #[serde(default)]
+    headers: Option<HashMap<String, String>>,
+    /// JSON bodies only, like `headers`.
+    #[serde(default)]
+    tags: Vec<String>,
+    expected_body_regex: Option<String>,
+    expected_content_type: Option<String>,
+    expected_content_encoding: Option<String>,
+    /// JSON bodies only, like `headers`.
+    #[serde(default)]
+    expected_headers: Option<HashMap<String, String>>,
+    basic_auth_user: Option<String>,
+    basic_auth_pass: Option<String>,
+    latency_threshold_ms: Option<i32>,
+    display_name: Option<String>,
+    max_history: Option<i32>,
+    expected_status_ranges: Option<String>,
+    /// JSON bodies only, like `headers`.
+    #[serde(default)]
+    metadata: Option<Metadata>,
+    insecure_skip_tls_verify: Option<bool>,
+    /// JSON bodies only, like `headers`.
+    #[serde(default)]
+    steps: Option<Vec<SequenceStep>>,
+    cron_schedule: Option<String>,
+    ip_family: Option<IpFamily>,
+}
+
+impl BulkTarget {
+    /// Validates the row as `POST /api/targets` would, filling in the defaults of blank settings.
+    fn validated(self) -> Result<CreateTarget, String> {
+        CreateTarget {
+            url: self.url,
+            check_type: self.check_type.unwrap_or_default(),
+            check_interval_secs: self.check_interval_secs,
+            timeout_ms: self.timeout_ms,
+            expected_status: self.expected_status,
+            headers: self.headers,
+            follow_redirects: self.follow_redirects,
+            expected_body_substring: self.expected_body_substring,
+            http_method: self.http_method,
+            request_body: self.request_body,
+            tags: self.tags,
+            expected_body_regex: self.expected_body_regex,
+            expected_content_type: self.expected_content_type,
+            expected_content_encoding: self.expected_content_encoding,
+            expected_headers: self.expected_headers,
+            basic_auth_user: self.basic_auth_user,
+            basic_auth_pass: self.basic_auth_pass,
+            latency_threshold_ms: self.latency_threshold_ms,
+            display_name: self.display_name,
+            max_history: self.max_history,
+            expected_status_ranges: self.expected_status_ranges,
+            metadata: self.metadata,
+            insecure_skip_tls_verify: self.insecure_skip_tls_verify.unwrap_or_default(),
+            steps: self.steps,
+            cron_schedule: self.cron_schedule,
+            ip_family: self.ip_family.unwrap_or_default(),
+        }
+        .validated()
+    }
+}
+
+/// A bulk import row number paired with the row, or the reason it could not be parsed.
+type BulkRow = (usize, Result<BulkTarget, String>);
+
+#[derive(Serialize, Default)]
+struct BulkSummary {
+    inserted: u64,
+    skipped: u64,
+    errors: Vec<BulkRowError>,
+}
+
+#[derive(Serialize)]
+struct BulkRowError {
+    /// 1-based position of the row in the submitted array or CSV body (excluding the header).
+    row: usize,
+    error: String,
+}
+
+/// One historical check imported from another monitoring tool.
+#[derive(Deserialize)]
+struct ImportedCheck {
+    checked_at: DateTime<Utc>,
+    /// Empty when the check got no response.
+    status_code: Option<i32>,
+    response_time_ms: Option<i32>,
+}
+
+impl ImportedCheck {
+    fn validated(self) -> Result<Self, String> {
+        if self.checked_at > Utc::now() {
+            return Err(format!("checked_at {} is in the future", self.checked_at.to_rfc3339()));
+        }
+        if self.status_code.is_some_and(|status| !(100..=599).contains(&status)) {
+            return Err("status_code must be a valid HTTP status code (100-599)".into());
+        }
+        if self.response_time_ms.is_some_and(|ms| ms < 0) {
+            return Err("response_time_ms must not be negative".into());
+        }
+        Ok(self)
+    }
+}
+
+/// An import row number paired with the check, or the reason it could not be parsed.
+type ImportRow = (usize, Result<ImportedCheck, String>);
+
+// Query parameters
+#[derive(Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct StatusQuery {
+    limit: Option<i64>,
+    offset: Option<i64>,
+    /// RFC3339 lower bound (inclusive) on `checked_at`.
+    from: Option<String>,
+    /// RFC3339 upper bound (inclusive) on `checked_at`.
+    to: Option<String>,
+    /// Only include checks made from this region.
+    region: Option<String>,
+}
+
+/// Page size used when `limit` is omitted.
+const DEFAULT_PAGE_LIMIT: i64 = 50;
+
+/// Largest accepted `limit`; larger values are rejected rather than clamped.
+const MAX_PAGE_LIMIT: i64 = 500;
+
+#[derive(Deserialize, Debug)]
+struct BatchStatusQuery {
+    /// Comma-separated target ids, at most `MAX_BATCH_IDS`.
+    ids: Option<String>,
+}
+
+/// Most ids accepted by one `GET /api/status?ids=` request.
+const MAX_BATCH_IDS: usize = 100;
+
+#[derive(Deserialize, Debug)]
+struct LimitQuery {
+    limit: Option<i64>,
+}
+
+#[derive(Deserialize, Debug)]
+struct AlertQuery {
+    /// Only include alerts for this target.
+    target_id: Option<i32>,
+    limit: Option<i64>,
+}
+
+#[derive(Deserialize, Debug)]
+struct WindowQuery {
+    /// Look-back window such as `30m`, `24h`, or `7d`.
+    window: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct TimeseriesQuery {
+    /// Look-back window such as `30m`, `24h`, or `7d`.
+    window: Option<String>,
+    /// Bucket width in the same format; must divide the window evenly.
+    bucket: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct AnomalyQuery {
+    /// Look-back window such as `30m`, `24h`, or `7d`.
+    window: Option<String>,
+    /// Standard deviations above the mean beyond which a check counts as anomalous (default `3`).
+    k: Option<f64>,
+}
+
+/// Key under which `/api/status/:target_id/codes` counts checks that got no status code.
+const ERROR_CODE_BUCKET: &str = "error";
+
+/// Threshold used when `k` is omitted from an anomalies request.
+const DEFAULT_ANOMALY_K: f64 = 3.0;
+
+/// Most buckets a timeseries request may return.
+const MAX_TIMESERIES_BUCKETS: i64 = 1_000;
+
+#[derive(Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct TagQuery {
+    /// Only include targets carrying this tag.
+    tag: Option<String>,
+    /// Only include targets whose metadata has this `key:value` pair, e.g. `owner:platform`.
+    label: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct DashboardQuery {
+    /// Only include targets carrying this tag.
+    tag: Option<String>,
+    /// `tag` returns an object of entries keyed by tag instead of a flat list.
+    group_by: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ExportQuery {
+    /// Optional RFC3339 lower bound on `checked_at` (inclusive).
+    from: Option<String>,
+    /// Optional RFC3339 upper bound on `checked_at` (inclusive).
+    to: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct UptimeQuery {
+    /// Look-back window such as `30m`, `24h`, or `7d`.
+    window: Option<String>,
+    /// Leave checks taken during maintenance windows out of the calculation.
+    #[serde(default)]
+    exclude_maintenance: bool,
+    /// Only include checks made from this region.
+    region: Option<String>,
+    /// `degraded` counts timed-out checks separately from `down` (default `down`).
+    #[serde(default)]
+    timeout_as: TimeoutAs,
+}
+
+#[derive(Deserialize, Debug)]
+struct TagUptimeQuery {
+    tag: String,
+    /// Look-back window such as `30m`, `24h`, or `7d`.
+    window: Option<String>,
+    #[serde(default)]
+    mode: UptimeMode,
+    /// Leave checks taken during maintenance windows out of the calculation.
+    #[serde(default)]
+    exclude_maintenance: bool,
+}
+
+#[derive(Deserialize, Debug)]
+struct EventsQuery {
+    /// Only stream checks of this target.
+    target_id: Option<i32>,
+}
+
+// Background worker settings, read from the environment at startup
+#[derive(Clone, Debug)]
+pub struct WorkerConfig {
+    /// Maximum number of target checks in flight at once (`CHECK_CONCURRENCY`, default 10).
+    concurrency: usize,
+    /// Maximum number of checks in flight against any one host, so a slow host can't take up
+    /// every slot (`MAX_CHECKS_PER_HOST`, default 2).
+    max_checks_per_host: usize,
+    /// Extra attempts made after a failed request before recording a failure (`CHECK_RETRIES`, default 2).
+    retries: u32,
+    /// Age in days after which `health_checks` rows are purged (`RETENTION_DAYS`, default 30).
+    retention_days: i64,
+    /// Upper bound of the random delay before each check (`CHECK_JITTER_MS`, default 0 = none).
+    jitter_ms: u64,
+    /// Consecutive failed checks needed before a down alert is sent (`ALERT_AFTER_FAILURES`, default 1).
+    alert_after_failures: i32,
+    /// Weight of the newest sample in each target's latency moving average (`EMA_ALPHA`, default 0.2).
+    ema_alpha: f64,
+    /// Interval for targets without their own `check_interval_secs` (`CHECK_INTERVAL_SECS`, default 60).
+    check_interval: Duration,
+    /// Location recorded with every check, so workers in several regions can share one database
+    /// (`REGION`, default `default`).
+    region: String,
+    /// Largest response body drained by an HTTP check before it is aborted and recorded as failed
+    /// (`MAX_BODY_BYTES`, default 5 MB).
+    max_body_bytes: u64,
+    /// Wait before the worker starts, e.g. for dependencies to settle (`STARTUP_DELAY_SECS`, default 0).
+    startup_delay: Duration,
+    /// Run the first tick as soon as the worker starts rather than one tick interval later
+    /// (`RUN_FIRST_TICK_IMMEDIATELY`, default true).
+    run_first_tick_immediately: bool,
+}
+
+impl WorkerConfig {
+    pub fn from_env() -> anyhow::Result<Self> {
+        let concurrency = env_or("CHECK_CONCURRENCY", 10usize)?;
+        anyhow::ensure!(concurrency > 0, "CHECK_CONCURRENCY must be greater than zero");
+        let max_checks_per_host = env_or("MAX_CHECKS_PER_HOST", 2usize)?;
+        anyhow::ensure!(max_checks_per_host > 0, "MAX_CHECKS_PER_HOST must be greater than zero");
+        let retries = env_or("CHECK_RETRIES", 2u32)?;
+        let retention_days = env_or("RETENTION_DAYS", 30i64)?;
+        anyhow::ensure!(retention_days > 0, "RETENTION_DAYS must be greater than zero");
+        let jitter_ms = env_or("CHECK_JITTER_MS", 0u64)?;
+        let alert_after_failures = env_or("ALERT_AFTER_FAILURES", 1i32)?;
+        anyhow::ensure!(alert_after_failures > 0, "ALERT_AFTER_FAILURES must be greater than zero");
+        let ema_alpha = env_or("EMA_ALPHA", 0.2f64)?;
+        anyhow::ensure!(ema_alpha > 0.0 && ema_alpha <= 1.0, "EMA_ALPHA must be greater than 0 and at most 1");
+        let check_interval_secs = env_or("CHECK_INTERVAL_SECS", DEFAULT_CHECK_INTERVAL_SECS)?;
+        anyhow::ensure!(check_interval_secs > 0, "CHECK_INTERVAL_SECS must be greater than zero");
+        let region = env_or("REGION", DEFAULT_REGION.to_string())?;
+        anyhow::ensure!(!region.is_empty(), "REGION must not be empty");
+        let max_body_bytes = env_or("MAX_BODY_BYTES", DEFAULT_MAX_BODY_BYTES)?;
+        anyhow::ensure!(max_body_bytes > 0, "MAX_BODY_BYTES must be greater than zero");
+        let startup_delay_secs = env_or("STARTUP_DELAY_SECS", 0u64)?;
+        let run_first_tick_immediately = env_or("RUN_FIRST_TICK_IMMEDIATELY", true)?;
+        Ok(Self {
+            concurrency,
+            max_checks_per_host,
+            retries,
+            retention_days,
+            jitter_ms,
+            alert_after_failures,
+            ema_alpha,
+            check_interval: Duration::from_secs(check_interval_secs),
+            region,
+            max_body_bytes,
+            startup_delay: Duration::from_secs(startup_delay_secs),
+            run_first_tick_immediately,
+        })
+    }
+
+    /// A worker that hasn't completed a tick for this long is reported as stale by `/health`. Based
+    /// on the check interval rather than [`WORKER_TICK`], since a tick lasts as long as its slowest check.
+    fn stale_after(&self) -> Duration {
+        self.check_interval * 3
+    }
+}
+
+/// Compiled `expected_body_regex` patterns keyed by target id, so each is compiled once rather
+/// than on every check.
+#[derive(Clone, Default)]
+struct BodyRegexCache(Arc<Mutex<HashMap<i32, Regex>>>);
+
+impl BodyRegexCache {
+    /// The target's compiled pattern, recompiled when the stored pattern has changed.
+    fn get(&self, t: &Target) -> Option<Regex> {
+        let pattern = t.expected_body_regex.as_deref()?;
+        let mut cache = self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(regex) = cache.get(&t.id).filter(|regex| regex.as_str() == pattern) {
+            return Some(regex.clone());
+        }
+        match Regex::new(pattern) {
+            Ok(regex) => {
+                cache.insert(t.id, regex.clone());
+                Some(regex)
+            }
+            Err(e) => {
+                // Patterns are validated on save, so this only happens for rows edited by hand
+                warn!(target = %t.log_host(), error = %e, "invalid expected_body_regex, skipping body assertion");
+                cache.remove(&t.id);
+                None
+            }
+        }
+    }
+
+    /// Drops patterns of targets that are no longer checked.
+    fn retain(&self, keep: impl Fn(i32) -> bool) {
+        self.0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .retain(|id, _| keep(*id));
+    }
+}
+
+/// One semaphore per checked host, limiting how many checks hit it at once.
+#[derive(Clone, Default)]
+struct HostLimits(Arc<Mutex<HashMap<String, Arc<Semaphore>>>>);
+
+impl HostLimits {
+    /// The host's semaphore, created with `permits` the first time the host is seen.
+    fn get(&self, host: &str, permits: usize) -> Arc<Semaphore> {
+        let mut limits = self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        limits.entry(host.to_string()).or_insert_with(|| Arc::new(Semaphore::new(permits))).clone()
+    }
+
+    /// Drops semaphores of hosts that are no longer checked.
+    fn retain(&self, keep: impl Fn(&str) -> bool) {
+        self.0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .retain(|host, _| keep(host));
+    }
+}
+
+/// When each target's burn rate was last computed, so checks recompute it at most once per
+/// [`BURN_RATE_REFRESH`].
+#[derive(Clone, Default)]
+struct BurnRateRefresh(Arc<Mutex<HashMap<i32, Instant>>>);
+
+impl BurnRateRefresh {
+    /// Whether the target's burn rate is due for recomputing; if so, it's marked as computed now.
+    fn due(&self, target_id: i32) -> bool {
+        let mut computed = self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let now = Instant::now();
+        match computed.get(&target_id) {
+            Some(at) if now.duration_since(*at) < BURN_RATE_REFRESH => false,
+            _ => {
+                computed.insert(target_id, now);
+                true
+            }
+        }
+    }
+
+    /// Drops entries of targets that are no longer checked.
+    fn retain(&self, keep: impl Fn(i32) -> bool) {
+        self.0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .retain(|id, _| keep(*id));
+    }
+}
+
+/// Progress of a manual `POST /api/check-all` sweep; only the latest one is kept.
+#[derive(Serialize, Clone)]
+struct Sweep {
+    job_id: String,
+    started_at: DateTime<Utc>,
+    /// `None` while checks are still running.
+    finished_at: Option<DateTime<Utc>>,
+    total: usize,
+    /// Checks recorded as healthy.
+    succeeded: usize,
+    /// Checks recorded as unhealthy, or that could not be recorded at all.
+    failed: usize,
+}
+
+/// Outcome of the most recent completed worker tick.
+struct Heartbeat {
+    last_tick_at: Option<DateTime<Utc>>,
+    last_tick_duration_ms: Option<u64>,
+    targets_checked: usize,
+    /// When the last tick finished, or startup if none has yet.
+    since: Instant,
+}
+
+/// Counters for the monitor's own throughput since startup, as opposed to per-target health.
+struct CheckStats {
+    started_at: DateTime<Utc>,
+    checks: AtomicU64,
+    failures: AtomicU64,
+    insert_errors: AtomicU64,
+}
+
+/// Body of `GET /api/worker/stats`.
+#[derive(Serialize)]
+struct CheckStatsReport {
+    started_at: DateTime<Utc>,
+    /// Checks performed by the worker and on demand, including ones that failed to be stored.
+    checks_total: u64,
+    /// Stored checks that came out unhealthy.
+    failures_total: u64,
+    /// Checks whose `health_checks` row could not be inserted.
+    insert_errors_total: u64,
+    /// `failures_total / checks_total`; `None` before the first check.
+    failure_rate: Option<f64>,
+}
+
+/// Body of `GET /api/worker`.
+#[derive(Serialize)]
+struct WorkerReport {
+    /// When the last tick finished; `None` until the first one completes.
+    last_tick_at: Option<DateTime<Utc>>,
+    last_tick_duration_ms: Option<u64>,
+    /// Targets that were due and checked during the last tick.
+    targets_checked: usize,
+    /// No tick has completed within [`WorkerConfig::stale_after`].
+    stale: bool,
+}
+
+/// Parses the environment variable `key`, returning `default` when it is unset.
+fn env_or<T>(key: &str, default: T) -> anyhow::Result<T>
+where
+    T: FromStr,
+    T::Err: std::fmt::Display,
+{
+    match std::env::var(key) {
+        Ok(raw) => raw
+            .trim()
+            .parse()
+            .map_err(|e| anyhow::anyhow!("invalid value for {key} ({raw:?}): {e}")),
+        Err(_) => Ok(default),
+    }
+}
+
+/// Flapping detection settings, shared by the API and the alerting path.
+#[derive(Clone, Copy, Debug)]
+struct FlapConfig {
+    /// Number of most recent checks inspected (`FLAP_WINDOW`, default 20).
+    window: i64,
+    /// Transitions above which a target counts as flapping (`FLAP_THRESHOLD`, default 5).
+    threshold: i64,
+}
+
+impl FlapConfig {
+    fn from_env() -> anyhow::Result<Self> {
+        let window = env_or("FLAP_WINDOW", 20i64)?;
+        anyhow::ensure!(window > 1, "FLAP_WINDOW must be greater than one");
+        let threshold = env_or("FLAP_THRESHOLD", 5i64)?;
+        anyhow::ensure!(threshold >= 0, "FLAP_THRESHOLD must not be negative");
+        Ok(Self { window, threshold })
+    }
+}
+
+/// Multi-window error-budget burn alerting, shared by the API and the alerting path.
+#[derive(Clone, Copy, Debug)]
+struct BurnRateConfig {
+    /// Share of checks expected to be healthy (`SLO_TARGET_PCT`, default 99.9).
+    slo_pct: f64,
+    /// Burn rate over the last hour above which a target may be burning (`BURN_RATE_SHORT_THRESHOLD`,
+    /// default 14.4, i.e. 2% of a 30-day budget in an hour).
+    short_threshold: f64,
+    /// Burn rate over the last six hours that must be exceeded too, so a brief spike doesn't page
+    /// (`BURN_RATE_LONG_THRESHOLD`, default 6, i.e. 5% of a 30-day budget in six hours).
+    long_threshold: f64,
+}
+
+impl BurnRateConfig {
+    fn from_env() -> anyhow::Result<Self> {
+        let slo_pct = env_or("SLO_TARGET_PCT", 99.9f64)?;
+        anyhow::ensure!(slo_pct > 0.0 && slo_pct < 100.0, "SLO_TARGET_PCT must be greater than 0 and less than 100");
+        let short_threshold = env_or("BURN_RATE_SHORT_THRESHOLD", 14.4f64)?;
+        anyhow::ensure!(short_threshold > 0.0, "BURN_RATE_SHORT_THRESHOLD must be greater than zero");
+        let long_threshold = env_or("BURN_RATE_LONG_THRESHOLD", 6.0f64)?;
+        anyhow::ensure!(long_threshold > 0.0, "BURN_RATE_LONG_THRESHOLD must be greater than zero");
+        Ok(Self { slo_pct, short_threshold, long_threshold })
+    }
+}
+
+/// Windows the burn rate is measured over.
+const BURN_RATE_SHORT_WINDOW_SECS: i64 = 60 * 60;
+const BURN_RATE_LONG_WINDOW_SECS: i64 = 6 * 60 * 60;
+
+/// How long a target's burn rate is reused before checks recompute it. A minute is small next to
+/// the short window, and keeps the six-hour aggregate from running on every check.
+const BURN_RATE_REFRESH: Duration = Duration::from_secs(60);
+
+/// Connection reuse and default request settings for the probe client.
+#[derive(Clone, Debug)]
+struct ClientConfig {
+    /// Idle connections kept open per host (`HTTP_POOL_MAX_IDLE_PER_HOST`, default 10).
+    pool_max_idle_per_host: usize,
+    /// TCP keep-alive interval for pooled connections (`HTTP_TCP_KEEPALIVE_SECS`, default 60, 0 = off).
+    tcp_keepalive: Option<Duration>,
+    /// Speak HTTP/2 without negotiating it first, also over plain HTTP (`HTTP2_PRIOR_KNOWLEDGE`,
+    /// default false). HTTPS targets negotiate HTTP/2 through ALPN either way.
+    http2_prior_knowledge: bool,
+    /// `User-Agent` sent with every check (`CHECK_USER_AGENT`, default `devops-health-monitor/<version>`).
+    user_agent: String,
+    /// Headers sent with every check, given as a JSON object (`CHECK_DEFAULT_HEADERS`). A target's
+    /// own headers win on conflict, since reqwest only adds defaults a request doesn't set.
+    default_headers: HeaderMap,
+}
+
+impl ClientConfig {
+    fn from_env() -> anyhow::Result<Self> {
+        let pool_max_idle_per_host = env_or("HTTP_POOL_MAX_IDLE_PER_HOST", 10usize)?;
+        let keepalive_secs = env_or("HTTP_TCP_KEEPALIVE_SECS", 60u64)?;
+        let http2_prior_knowledge = env_or("HTTP2_PRIOR_KNOWLEDGE", false)?;
+        let user_agent = env_or("CHECK_USER_AGENT", DEFAULT_USER_AGENT.to_string())?;
+        HeaderValue::from_str(&user_agent).context("invalid CHECK_USER_AGENT")?;
+        let default_headers = match std::env::var("CHECK_DEFAULT_HEADERS") {
+            Ok(raw) => {
+                let headers: HashMap<String, String> =
+                    serde_json::from_str(&raw).context("CHECK_DEFAULT_HEADERS must be a JSON object of header names to values")?;
+                validate_headers(&headers).map_err(|e| anyhow::anyhow!("invalid CHECK_DEFAULT_HEADERS: {e}"))?;
+                headers
+                    .iter()
+                    .map(|(name, value)| -> anyhow::Result<_> {
+                        Ok((HeaderName::from_bytes(name.as_bytes())?, HeaderValue::from_str(value)?))
+                    })
+                    .collect::<anyhow::Result<HeaderMap>>()?
+            }
+            Err(_) => HeaderMap::new(),
+        };
+        Ok(Self {
+            pool_max_idle_per_host,
+            tcp_keepalive: (keepalive_secs > 0).then(|| Duration::from_secs(keepalive_secs)),
+            http2_prior_knowledge,
+            user_agent,
+            default_headers,
+        })
+    }
+
+    /// The probe client. Timeouts are applied per request from each target's settings, and
+    /// redirects are followed per target in `probe_http`. `accept_invalid_certs` builds the client
+    /// for targets with `insecure_skip_tls_verify`. Binding to the unspecified address of one
+    /// family makes the connector skip the host's addresses of the other.
+    fn build(&self, accept_invalid_certs: bool, family: IpFamily) -> anyhow::Result<reqwest::Client> {
+        let mut builder = reqwest::Client::builder()
+            .danger_accept_invalid_certs(accept_invalid_certs)
+            .redirect(reqwest::redirect::Policy::none())
+            // `read_body` decodes responses itself so their encoding can be recorded
+            .no_gzip()
+            .no_brotli()
+            .no_deflate()
+            .pool_max_idle_per_host(self.pool_max_idle_per_host)
+            .tcp_keepalive(self.tcp_keepalive)
+            .user_agent(&self.user_agent)
+            .default_headers(self.default_headers.clone());
+        if self.http2_prior_knowledge {
+            builder = builder.http2_prior_knowledge();
+        }
+        builder = match family {
+            IpFamily::Auto => builder,
+            IpFamily::V4 => builder.local_address(IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
+            IpFamily::V6 => builder.local_address(IpAddr::V6(Ipv6Addr::UNSPECIFIED)),
+        };
+        builder.build().context("failed to build HTTP client")
+    }
+}
+
+/// A probe client for every combination of certificate policy and address family, since one
+/// client can't mix either. None of them follows redirects itself (see `probe_http`).
+#[derive(Clone)]
+struct ProbeClients {
+    /// Indexed by [`IpFamily`].
+    verified: [reqwest::Client; 3],
+    /// Accepting invalid certificates, for `insecure_skip_tls_verify` targets.
+    insecure: [reqwest::Client; 3],
+}
+
+impl ProbeClients {
+    fn build(config: &ClientConfig) -> anyhow::Result<Self> {
+        let build = |insecure| -> anyhow::Result<[reqwest::Client; 3]> {
+            Ok([
+                config.build(insecure, IpFamily::Auto)?,
+                config.build(insecure, IpFamily::V4)?,
+                config.build(insecure, IpFamily::V6)?,
+            ])
+        };
+        Ok(Self { verified: build(false)?, insecure: build(true)? })
+    }
+
+    fn get(&self, insecure: bool, family: IpFamily) -> &reqwest::Client {
+        let clients = if insecure { &self.insecure } else { &self.verified };
+        &clients[family as usize]
+    }
+}
+
+// Shared application state
+#[derive(Clone)]
+pub struct AppState {
+    pool: PgPool,
+    metrics: PrometheusHandle,
+    alerter: Alerter,
+    flap: FlapConfig,
+    burn: BurnRateConfig,
+    /// Serialized [`LiveCheck`] messages fanned out to `/api/live` and `/api/events` connections.
+    live: broadcast::Sender<LiveUpdate>,
+    /// Clients used for every probe, picked by the target's TLS and address family settings.
+    clients: ProbeClients,
+    worker: WorkerConfig,
+    heartbeat: Arc<Mutex<Heartbeat>>,
+    body_regexes: BodyRegexCache,
+    host_limits: HostLimits,
+    burn_refresh: BurnRateRefresh,
+    stats: Arc<CheckStats>,
+    /// Set once the first worker tick completes. The state only exists after migrations and
+    /// seeding, so this marks the end of startup for `/ready`.
+    ready: Arc<AtomicBool>,
+    /// The latest manual sweep; a new one is refused while this one is unfinished.
+    sweep: Arc<Mutex<Option<Sweep>>>,
+    /// Verifies the signature on `POST /api/targets/:target_id/deployed`.
+    deploy_secret: WebhookSecret,
+}
+
+impl AppState {
+    /// Sets up alerting, flapping detection, the live feed, and the probe client from the environment.
+    pub fn new(pool: PgPool, metrics: PrometheusHandle, worker: WorkerConfig) -> anyhow::Result<Self> {
+        let alerter = Alerter::from_env().context("failed to set up alerting")?;
+        let flap = FlapConfig::from_env().context("invalid flapping configuration")?;
+        let burn = BurnRateConfig::from_env().context("invalid burn rate configuration")?;
+        let (live, _) = broadcast::channel(LIVE_CHANNEL_CAPACITY);
+        let client_config = ClientConfig::from_env().context("invalid HTTP client configuration")?;
+        let clients = ProbeClients::build(&client_config)?;
+        let heartbeat = Arc::new(Mutex::new(Heartbeat {
+            last_tick_at: None,
+            last_tick_duration_ms: None,
+            targets_checked: 0,
+            since: Instant::now(),
+        }));
+        let body_regexes = BodyRegexCache::default();
+        Ok(Self {
+            pool,
+            metrics,
+            alerter,
+            flap,
+            burn,
+            live,
+            clients,
+            worker,
+            heartbeat,
+            body_regexes,
+            host_limits: HostLimits::default(),
+            burn_refresh: BurnRateRefresh::default(),
+            stats: Arc::new(CheckStats {
+                started_at: Utc::now(),
+                checks: AtomicU64::new(0),
+                failures: AtomicU64::new(0),
+                insert_errors: AtomicU64::new(0),
+            }),
+            ready: Arc::new(AtomicBool::new(false)),
+            sweep: Arc::default(),
+            deploy_secret: WebhookSecret::from_env(),
+        })
+    }
+
+    fn worker_report(&self) -> WorkerReport {
+        let heartbeat = self.heartbeat.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        WorkerReport {
+            last_tick_at: heartbeat.last_tick_at,
+            last_tick_duration_ms: heartbeat.last_tick_duration_ms,
+            targets_checked: heartbeat.targets_checked,
+            stale: heartbeat.since.elapsed() > self.worker.stale_after(),
+        }
+    }
+
+    fn stats_report(&self) -> CheckStatsReport {
+        let checks_total = self.stats.checks.load(Ordering::Relaxed);
+        let failures_total = self.stats.failures.load(Ordering::Relaxed);
+        CheckStatsReport {
+            started_at: self.stats.started_at,
+            checks_total,
+            failures_total,
+            insert_errors_total: self.stats.insert_errors.load(Ordering::Relaxed),
+            failure_rate: (checks_total > 0).then(|| failures_total as f64 / checks_total as f64),
+        }
+    }
+
+    fn record_tick(&self, started: Instant, targets_checked: usize) {
+        let mut heartbeat = self.heartbeat.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        *heartbeat = Heartbeat {
+            last_tick_at: Some(Utc::now()),
+            last_tick_duration_ms: Some(started.elapsed().as_millis() as u64),
+            targets_checked,
+            since: Instant::now(),
+        };
+        self.ready.store(true, Ordering::Relaxed);
+    }
+}
+
+// --------- Routes ---------
+
+/// Response for a failed query. When no connection freed up within `DB_ACQUIRE_TIMEOUT_SECS` the
+/// pool is exhausted rather than broken, so clients get a retryable 503 instead of a 500.
+fn db_error(e: &(dyn std::error::Error + 'static)) -> Response {
+    match e.downcast_ref::<sqlx::Error>() {
+        Some(sqlx::Error::PoolTimedOut) => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            [(header::RETRY_AFTER, "1")],
+            "database busy: no connection available, try again shortly",
+        )
+            .into_response(),
+        _ => (StatusCode::INTERNAL_SERVER_ERROR, "DB error").into_response(),
+    }
+}
+
+/// List targets
+#[utoipa::path(
+    get,
+    path = "/api/targets",
+    params(TagQuery),
+    responses(
+        (status = 200, description = "Targets ordered by id", body = [Target]),
+        (status = 400, description = "Invalid label filter", body = String),
+    ),
+)]
+#[instrument(skip(state))]
+async fn list_targets(Query(query): Query<TagQuery>, State(state): State<AppState>) -> impl IntoResponse {
+    let label = match query.label.as_deref().map(parse_label).transpose() {
+        Ok(label) => label,
+        Err(msg) => return (StatusCode::BAD_REQUEST, msg).into_response(),
+    };
+    match fetch_targets(&state.pool, query.tag.as_deref(), label).await {
+        Ok(targets) => (StatusCode::OK, Json(targets)).into_response(),
+        Err(e) => {
+            error!(error = %e, "failed to fetch targets");
+            db_error(&e)
+        }
+    }
+}
+
+/// Splits a `key:value` label filter at its first colon, so values may contain colons.
+fn parse_label(raw: &str) -> Result<(&str, &str), String> {
+    raw.split_once(':')
+        .filter(|(key, _)| !key.is_empty())
+        .ok_or_else(|| format!("invalid label '{raw}': expected key:value"))
+}
+
+/// Targets ordered by id, optionally only those carrying `tag` and whose metadata contains the
+/// `(key, value)` string pair.
+async fn fetch_targets(pool: &PgPool, tag: Option<&str>, label: Option<(&str, &str)>) -> sqlx::Result<Vec<Target>> {
+    let label = label.map(|(key, value)| SqlJson(serde_json::json!({ key: value })));
+    sqlx::query_as::<_, Target>(&format!(
+        r#"
+        SELECT {TARGET_COLUMNS} FROM targets
+        WHERE ($1::text IS NULL OR tags @> ARRAY[$1])
+          AND ($2::jsonb IS NULL OR metadata @> $2)
+        ORDER BY id
+        "#
+    ))
+    .bind(tag)
+    .bind(label)
+    .fetch_all(pool)
+    .await
+}
+
+async fn fetch_target(pool: &PgPool, target_id: i32) -> sqlx::Result<Option<Target>> {
+    sqlx::query_as::<_, Target>(&format!("SELECT {TARGET_COLUMNS} FROM targets WHERE id = $1"))
+        .bind(target_id)
+        .fetch_optional(pool)
+        .await
+}
+
+/// Page through a target's check history
+#[utoipa::path(
+    get,
+    path = "/api/status/{target_id}",
+    params(("target_id" = i32, Path, description = "Target id"), StatusQuery),
+    responses(
+        (status = 200, description = "Checks newest first", body = StatusPage),
+        (status = 400, description = "Invalid paging or time range", body = String),
+    ),
+)]
+#[instrument(skip(state))]
+async fn get_status(
+    Path(target_id): Path<i32>,
+    Query(query): Query<StatusQuery>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_LIMIT);
+    let offset = query.offset.unwrap_or(0);
+    if !(1..=MAX_PAGE_LIMIT).contains(&limit) {
+        return (StatusCode::BAD_REQUEST, format!("limit must be between 1 and {MAX_PAGE_LIMIT}")).into_response();
+    }
+    if offset < 0 {
+        return (StatusCode::BAD_REQUEST, "offset must not be negative").into_response();
+    }
+    let (from, to) = match (parse_timestamp("from", query.from.as_deref()), parse_timestamp("to", query.to.as_deref())) {
+        (Ok(from), Ok(to)) => (from, to),
+        (Err(msg), _) | (_, Err(msg)) => return (StatusCode::BAD_REQUEST, msg).into_response(),
+    };
+
+    let total = sqlx::query_scalar::<_, i64>(
+        r#"
+        SELECT COUNT(*) FROM health_checks
+        WHERE target_id = $1
+          AND ($2::timestamptz IS NULL OR checked_at >= $2)
+          AND ($3::timestamptz IS NULL OR checked_at <= $3)
+          AND ($4::text IS NULL OR region = $4)
+        "#
+    )
+    .bind(target_id)
+    .bind(from)
+    .bind(to)
+    .bind(query.region.as_deref())
+    .fetch_one(&state.pool)
+    .await;
+
+    let rows = fetch_history(&state.pool, target_id, limit, offset, from, to, query.region.as_deref()).await;
+
+    match total.and_then(|total| rows.map(|rows| (total, rows))) {
+        Ok((total, rows)) => {
+            let records: Vec<StatusRecord> = rows.into_iter().map(StatusRecord::from).collect();
+            let has_more = offset + (records.len() as i64) < total;
+            let page = StatusPage { records, total, limit, offset, has_more };
+            (StatusCode::OK, Json(page)).into_response()
+        }
+        Err(e) => {
+            error!(error = %e, "failed to fetch health check records");
+            db_error(&e)
+        }
+    }
+}
+
+/// Latest check of several targets in one request. Unknown ids are reported under `missing`
+/// rather than failing the whole request.
+#[instrument(skip(state))]
+async fn get_batch_status(Query(query): Query<BatchStatusQuery>, State(state): State<AppState>) -> impl IntoResponse {
+    let ids = match parse_ids(query.ids.as_deref().unwrap_or_default()) {
+        Ok(ids) => ids,
+        Err(msg) => return (StatusCode::BAD_REQUEST, msg).into_response(),
+    };
+
+    let existing = sqlx::query_scalar::<_, i32>("SELECT id FROM targets WHERE id = ANY($1)")
+        .bind(&ids)
+        .fetch_all(&state.pool)
+        .await;
+    let latest = sqlx::query_as::<_, StatusRow>(
+        r#"
+        SELECT DISTINCT ON (h.target_id)
+            h.id, h.target_id, h.checked_at, h.status_code, h.response_time_ms, h.ttfb_ms, h.content_length_bytes, h.redirect_count, h.body_match, h.dns_ms,
+            h.http_version, h.error_kind, h.failed_step, h.content_type_match, h.headers_match, h.content_encoding, h.encoding_match, h.resolved_ip, h.region, t.expected_status, t.expected_status_ranges
+        FROM health_checks h
+        JOIN targets t ON t.id = h.target_id
+        WHERE h.target_id = ANY($1)
+        ORDER BY h.target_id, h.checked_at DESC
+        "#,
+    )
+    .bind(&ids)
+    .fetch_all(&state.pool)
+    .await;
+
+    match existing.and_then(|existing| latest.map(|latest| (existing, latest))) {
+        Ok((existing, latest)) => {
+            let mut statuses: BTreeMap<i32, Option<StatusRecord>> = existing.into_iter().map(|id| (id, None)).collect();
+            for row in latest {
+                let record = StatusRecord::from(row);
+                statuses.insert(record.record.target_id, Some(record));
+            }
+            let missing = ids.into_iter().filter(|id| !statuses.contains_key(id)).collect();
+            (StatusCode::OK, Json(BatchStatus { statuses, missing })).into_response()
+        }
+        Err(e) => {
+            error!(error = %e, "failed to fetch batch status");
+            db_error(&e)
+        }
+    }
+}
+
+/// Parses a comma-separated id list, dropping duplicates and rejecting more than `MAX_BATCH_IDS`.
+fn parse_ids(raw: &str) -> Result<Vec<i32>, String> {
+    let mut ids: Vec<i32> = Vec::new();
+    for part in raw.split(',').map(str::trim).filter(|part| !part.is_empty()) {
+        let id = part.parse::<i32>().map_err(|_| format!("invalid target id '{part}'"))?;
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+    if ids.is_empty() {
+        return Err("ids must list at least one target id, e.g. ?ids=1,2,5".into());
+    }
+    if ids.len() > MAX_BATCH_IDS {
+        return Err(format!("at most {MAX_BATCH_IDS} ids may be requested at once"));
+    }
+    Ok(ids)
+}
+
+/// Streams a target's checks as CSV, oldest first, without buffering the whole range.
+#[instrument(skip(state))]
+async fn export_status_csv(
+    Path(target_id): Path<i32>,
+    Query(query): Query<ExportQuery>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let (from, to) = match (parse_timestamp("from", query.from.as_deref()), parse_timestamp("to", query.to.as_deref())) {
+        (Ok(from), Ok(to)) => (from, to),
+        (Err(msg), _) | (_, Err(msg)) => return (StatusCode::BAD_REQUEST, msg).into_response(),
+    };
+
+    // Rows are fetched by a separate task and handed over through a small bounded channel, so
+    // the database cursor only advances as fast as the client reads
+    let (mut tx, rx) = futures::channel::mpsc::channel::<Result<Bytes, std::io::Error>>(EXPORT_CHANNEL_CAPACITY);
+    tokio::spawn(async move {
+        let header_row = ["id", "target_id", "checked_at", "status_code", "response_time_ms"].map(String::from);
+        if tx.send(Ok(encode_csv_row(&header_row))).await.is_err() {
+            return;
+        }
+
+        let mut rows = sqlx::query_as::<_, (i32, i32, DateTime<Utc>, Option<i32>, Option<i32>)>(
+            r#"
+            SELECT id, target_id, checked_at, status_code, response_time_ms
+            FROM health_checks
+            WHERE target_id = $1
+              AND ($2::timestamptz IS NULL OR checked_at >= $2)
+              AND ($3::timestamptz IS NULL OR checked_at <= $3)
+            ORDER BY checked_at
+            "#,
+        )
+        .bind(target_id)
+        .bind(from)
+        .bind(to)
+        .fetch(&state.pool);
+
+        loop {
+            let chunk = match rows.next().await {
+                Some(Ok((id, target_id, checked_at, status_code, response_time_ms))) => {
+                    let record = [
+                        id.to_string(),
+                        target_id.to_string(),
+                        checked_at.to_rfc3339(),
+                        status_code.map(|v| v.to_string()).unwrap_or_default(),
+                        response_time_ms.map(|v| v.to_string()).unwrap_or_default(),
+                    ];
+                    Ok(encode_csv_row(&record))
+                }
+                Some(Err(e)) => {
+                    error!(target_id, error = %e, "failed to stream health checks");
+                    Err(std::io::Error::other(e))
+                }
+                None => break,
+            };
+            let failed = chunk.is_err();
+            // A send error means the client went away
+            if tx.send(chunk).await.is_err() || failed {
+                break;
+            }
+        }
+    });
+
+    (
+        [
+            (header::CONTENT_TYPE, "text/csv".to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"target-{target_id}-checks.csv\"")),
+        ],
+        axum::body::Body::from_stream(rx),
+    )
+        .into_response()
+}
+
+/// Encodes one CSV record, quoting fields as needed.
+fn encode_csv_row(fields: &[String]) -> Bytes {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    // Writing to an in-memory buffer cannot fail
+    let _ = writer.write_record(fields);
+    Bytes::from(writer.into_inner().unwrap_or_default())
+}
+
+/// Fetches a target's checks, newest first, optionally bounded by `from`/`to`.
+async fn fetch_history(
+    pool: &PgPool,
+    target_id: i32,
+    limit: i64,
+    offset: i64,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+    region: Option<&str>,
+) -> sqlx::Result<Vec<StatusRow>> {
+    sqlx::query_as::<_, StatusRow>(
+        r#"
+        SELECT h.id, h.target_id, h.checked_at, h.status_code, h.response_time_ms, h.ttfb_ms, h.content_length_bytes, h.redirect_count, h.body_match, h.dns_ms,
+            h.http_version, h.error_kind, h.failed_step, h.content_type_match, h.headers_match, h.content_encoding, h.encoding_match, h.resolved_ip, h.region, t.expected_status, t.expected_status_ranges
+        FROM health_checks h
+        JOIN targets t ON t.id = h.target_id
+        WHERE h.target_id = $1
+          AND ($4::timestamptz IS NULL OR h.checked_at >= $4)
+          AND ($5::timestamptz IS NULL OR h.checked_at <= $5)
+          AND ($6::text IS NULL OR h.region = $6)
+        ORDER BY h.checked_at DESC
+        LIMIT $2 OFFSET $3
+        "#
+    )
+    .bind(target_id)
+    .bind(limit)
+    .bind(offset)
+    .bind(from)
+    .bind(to)
+    .bind(region)
+    .fetch_all(pool)
+    .await
+}
+
+#[instrument(skip(state))]
+async fn get_flapping(Path(target_id): Path<i32>, State(state): State<AppState>) -> impl IntoResponse {
+    let exists = sqlx::query_scalar::<_, bool>(r#"SELECT EXISTS (SELECT 1 FROM targets WHERE id = $1)"#)
+        .bind(target_id)
+        .fetch_one(&state.pool)
+        .await;
+
+    match exists {
+        Ok(true) => {}
+        Ok(false) => return (StatusCode::NOT_FOUND, "target not found").into_response(),
+        Err(e) => {
+            error!(error = %e, "failed to look up target");
+            return db_error(&e);
+        }
+    }
+
+    match flap_report(&state.pool, state.flap, target_id).await {
+        Ok(report) => (StatusCode::OK, Json(report)).into_response(),
+        Err(e) => {
+            error!(error = %e, "failed to compute flap report");
+            db_error(&e)
+        }
+    }
+}
+
+/// Counts healthy/unhealthy transitions over the target's last `config.window` checks.
+async fn flap_report(pool: &PgPool, config: FlapConfig, target_id: i32) -> sqlx::Result<FlapReport> {
+    let rows = fetch_history(pool, target_id, config.window, 0, None, None, None).await?;
+    let healthy: Vec<bool> = rows
+        .iter()
+        .map(|row| {
+            is_check_healthy(
+                row.record.status_code,
+                row.expected_status,
+                row.expected_status_ranges.as_ref(),
+                &[row.record.body_match, row.record.content_type_match, row.record.headers_match, row.record.encoding_match],
+            )
+        })
+        .collect();
+    let transitions = healthy.windows(2).filter(|pair| pair[0] != pair[1]).count() as i64;
+    let flap_score = if healthy.len() > 1 { transitions as f64 / (healthy.len() - 1) as f64 } else { 0.0 };
+
+    Ok(FlapReport {
+        target_id,
+        checks: healthy.len() as i64,
+        transitions,
+        flap_score,
+        is_flapping: transitions > config.threshold,
+    })
+}
+
+#[instrument(skip(state))]
+async fn get_burn_rate(Path(target_id): Path<i32>, State(state): State<AppState>) -> impl IntoResponse {
+    let exists = sqlx::query_scalar::<_, bool>(r#"SELECT EXISTS (SELECT 1 FROM targets WHERE id = $1)"#)
+        .bind(target_id)
+        .fetch_one(&state.pool)
+        .await;
+
+    match exists {
+        Ok(true) => {}
+        Ok(false) => return (StatusCode::NOT_FOUND, "target not found").into_response(),
+        Err(e) => {
+            error!(error = %e, "failed to look up target");
+            return db_error(&e);
+        }
+    }
+
+    match burn_rate_report(&state.pool, state.burn, target_id).await {
+        Ok(report) => (StatusCode::OK, Json(report)).into_response(),
+        Err(e) => {
+            error!(error = %e, "failed to compute burn rate");
+            db_error(&e)
+        }
+    }
+}
+
+/// Failure rates over the short and long windows as multiples of the error budget.
+async fn burn_rate_report(pool: &PgPool, config: BurnRateConfig, target_id: i32) -> sqlx::Result<BurnRateReport> {
+    let (short_checks, short_failed, long_checks, long_failed) = sqlx::query_as::<_, (i64, i64, i64, i64)>(
+        r#"
+        WITH checks AS (
+            SELECT
+                h.checked_at,
+                NOT check_healthy(h, t) AS failed
+            FROM health_checks h
+            JOIN targets t ON t.id = h.target_id
+            WHERE h.target_id = $1 AND h.checked_at >= NOW() - ($3 * INTERVAL '1 second')
+        )
+        SELECT
+            COUNT(*) FILTER (WHERE checked_at >= NOW() - ($2 * INTERVAL '1 second')),
+            COUNT(*) FILTER (WHERE checked_at >= NOW() - ($2 * INTERVAL '1 second') AND failed),
+            COUNT(*),
+            COUNT(*) FILTER (WHERE failed)
+        FROM checks
+        "#,
+    )
+    .bind(target_id)
+    .bind(BURN_RATE_SHORT_WINDOW_SECS)
+    .bind(BURN_RATE_LONG_WINDOW_SECS)
+    .fetch_one(pool)
+    .await?;
+
+    let budget = 1.0 - config.slo_pct / 100.0;
+    let burn_rate = |checks: i64, failed: i64| {
+        (checks > 0).then(|| ((failed as f64 / checks as f64 / budget) * 100.0).round() / 100.0)
+    };
+    let short_burn_rate = burn_rate(short_checks, short_failed);
+    let long_burn_rate = burn_rate(long_checks, long_failed);
+    Ok(BurnRateReport {
+        target_id,
+        slo_pct: config.slo_pct,
+        short_window_secs: BURN_RATE_SHORT_WINDOW_SECS,
+        long_window_secs: BURN_RATE_LONG_WINDOW_SECS,
+        short_burn_rate,
+        long_burn_rate,
+        burning: short_burn_rate.is_some_and(|rate| rate > config.short_threshold)
+            && long_burn_rate.is_some_and(|rate| rate > config.long_threshold),
+    })
+}
+
+/// Add a target
+#[utoipa::path(
+    post,
+    path = "/api/targets",
+    request_body = CreateTarget,
+    responses(
+        (status = 201, description = "Target created", body = Target),
+        (status = 400, description = "Invalid target settings", body = String),
+        (status = 409, description = "A target with this URL already exists", body = String),
+    ),
+    security(("api_key" = [])),
+)]
+#[instrument(skip(state, payload))]
+async fn create_target(State(state): State<AppState>, Json(payload): Json<CreateTarget>) -> impl IntoResponse {
+    let payload = match payload.validated() {
+        Ok(payload) => payload,
+        Err(msg) => return (StatusCode::BAD_REQUEST, msg).into_response(),
+    };
+
+    let row = sqlx::query_as::<_, Target>(&format!(
+        r#"
+        INSERT INTO targets (
+            url, check_type, headers, check_interval_secs, timeout_ms, expected_status, follow_redirects,
+            expected_body_substring, http_method, request_body, tags, expected_body_regex, expected_content_type,
+            basic_auth_user, basic_auth_pass, latency_threshold_ms, display_name, max_history, expected_status_ranges,
+            metadata, insecure_skip_tls_verify, steps, expected_headers, cron_schedule, ip_family, expected_content_encoding
+        )
+        VALUES (
+            $1, $2, $3, $4, $5, COALESCE($6, 200), COALESCE($7, true), $8, $9, $10, $11, $12, $13,
+            $14, $15, $16, $17, $18, $19, $20, $21, $22, $23, $24, $25, $26
+        )
+        ON CONFLICT DO NOTHING
+        RETURNING {TARGET_COLUMNS}
+        "#
+    ))
+    .bind(payload.url)
+    .bind(payload.check_type.as_str())
+    .bind(payload.headers.map(SqlJson))
+    .bind(payload.check_interval_secs)
+    .bind(payload.timeout_ms)
+    .bind(payload.expected_status)
+    .bind(payload.follow_redirects)
+    .bind(payload.expected_body_substring)
+    .bind(payload.http_method)
+    .bind(payload.request_body)
+    .bind(payload.tags)
+    .bind(payload.expected_body_regex)
+    .bind(payload.expected_content_type)
+    .bind(payload.basic_auth_user)
+    .bind(payload.basic_auth_pass)
+    .bind(payload.latency_threshold_ms)
+    .bind(payload.display_name)
+    .bind(payload.max_history)
+    .bind(payload.expected_status_ranges)
+    .bind(SqlJson(payload.metadata.unwrap_or_default()))
+    .bind(payload.insecure_skip_tls_verify)
+    .bind(payload.steps.map(SqlJson))
+    .bind(payload.expected_headers.map(SqlJson))
+    .bind(payload.cron_schedule)
+    .bind(payload.ip_family.as_str())
+    .bind(payload.expected_content_encoding)
+    .fetch_optional(&state.pool)
+    .await;
+
+    match row {
+        Ok(Some(target)) => {
+            info!(target_id = target.id, host = %target.log_host(), "target created");
+            (StatusCode::CREATED, Json(target)).into_response()
+        }
+        Ok(None) => (StatusCode::CONFLICT, "target with this URL already exists").into_response(),
+        Err(e) => {
+            error!(error = %e, "failed to insert target");
+            db_error(&e)
+        }
+    }
+}
+
+/// Probes a target's settings once, without saving the target or the check, so a typo in a URL
+/// shows up before it is saved. Settings are validated as for `POST /api/targets`, and the check
+/// is not retried.
+#[instrument(skip(state, payload))]
+async fn validate_target(State(state): State<AppState>, Json(payload): Json<CreateTarget>) -> impl IntoResponse {
+    let target = match payload.validated() {
+        Ok(payload) => payload.draft(),
+        Err(msg) => return (StatusCode::BAD_REQUEST, msg).into_response(),
+    };
+    let body_regex = target.expected_body_regex.as_deref().and_then(|pattern| Regex::new(pattern).ok());
+    let client = state.clients.get(target.insecure_skip_tls_verify, target.ip_family);
+    let outcome = probe_with_retries(client, &target, body_regex.as_ref(), 0, Uuid::new_v4(), state.worker.max_body_bytes).await;
+
+    let validation = match outcome {
+        Ok(outcome) => TargetValidation {
+            healthy: is_check_healthy(
+                outcome.status,
+                target.expected_status,
+                target.expected_status_ranges.as_ref(),
+                &[outcome.body_match, outcome.content_type_match, outcome.headers_match, outcome.encoding_match],
+            ),
+            status_code: outcome.status,
+            response_time_ms: outcome.latency_ms,
+            final_url: outcome.final_url,
+            content_type: outcome.content_type,
+            error_kind: None,
+            error: None,
+        },
+        Err(err) => TargetValidation {
+            healthy: false,
+            status_code: None,
+            response_time_ms: None,
+            final_url: None,
+            content_type: None,
+            error_kind: Some(err.kind()),
+            error: Some(err.to_string()),
+        },
+    };
+    info!(host = %target.log_host(), healthy = validation.healthy, "target validated");
+    (StatusCode::OK, Json(validation)).into_response()
+}
+
+/// Change some of a target's settings
+#[utoipa::path(
+    patch,
+    path = "/api/targets/{target_id}",
+    params(("target_id" = i32, Path, description = "Target id")),
+    request_body = UpdateTarget,
+    responses(
+        (status = 200, description = "Target updated", body = Target),
+        (status = 400, description = "Invalid target settings", body = String),
+        (status = 404, description = "Target not found", body = String),
+        (status = 409, description = "A target with this URL already exists", body = String),
+    ),
+    security(("api_key" = [])),
+)]
+#[instrument(skip(state, payload))]
+async fn update_target(
+    Path(target_id): Path<i32>,
+    State(state): State<AppState>,
+    Json(mut payload): Json<UpdateTarget>,
+) -> impl IntoResponse {
+    if payload.is_empty() {
+        return (StatusCode::BAD_REQUEST, "request body must contain at least one field to update").into_response();
+    }
+
+    let existing = match fetch_target(&state.pool, target_id).await {
+        Ok(Some(target)) => target,
+        Ok(None) => return (StatusCode::NOT_FOUND, "target not found").into_response(),
+        Err(e) => {
+            error!(error = %e, "failed to fetch target");
+            return db_error(&e);
+        }
+    };
+
+    // Re-validate the address whenever it or the check type changes
+    let check_type = payload.check_type.unwrap_or(existing.check_type);
+    let url = if payload.url.is_some() || payload.check_type.is_some() {
+        match validate_address(check_type, payload.url.as_deref().unwrap_or(&existing.url)) {
+            Ok(url) => Some(url),
+            Err(msg) => return (StatusCode::BAD_REQUEST, msg).into_response(),
+        }
+    } else {
+        None
+    };
+    if let Err(msg) = payload.fields().validate() {
+        return (StatusCode::BAD_REQUEST, msg).into_response();
+    }
+    // Validate the method and body together since either may come from the stored target
+    let http_method = if payload.http_method.is_some() || payload.request_body.is_some() {
+        let method = payload.http_method.as_deref().unwrap_or(&existing.http_method);
+        let body = payload.request_body.as_ref().map_or(existing.request_body.as_deref(), Option::as_deref);
+        match validate_http_method(method, body) {
+            Ok(method) => payload.http_method.is_some().then_some(method),
+            Err(msg) => return (StatusCode::BAD_REQUEST, msg).into_response(),
+        }
+    } else {
+        None
+    };
+    // Check the credentials as they will be stored, combining the update with the current values
+    if payload.basic_auth_user.is_some() || payload.basic_auth_pass.is_some() {
+        let user = payload.basic_auth_user.as_ref().map_or(existing.basic_auth_user.as_deref(), Option::as_deref);
+        let pass = payload.basic_auth_pass.as_ref().map_or(existing.basic_auth_pass.as_deref(), Option::as_deref);
+        if let Err(msg) = validate_basic_auth(user, pass) {
+            return (StatusCode::BAD_REQUEST, msg).into_response();
+        }
+    }
+    // Steps are resolved against the URL, so re-validate them whenever either or the check type changes
+    let steps = if payload.steps.is_some() || payload.url.is_some() || payload.check_type.is_some() {
+        let mut steps = match payload.steps {
+            Some(steps) => steps,
+            // A target leaving the sequence type drops the steps it no longer runs
+            None if check_type != CheckType::Sequence => None,
+            None => existing.steps.map(|steps| steps.0),
+        };
+        match validate_steps(check_type, steps.as_mut(), url.as_deref().unwrap_or(&existing.url)) {
+            Ok(()) => Some(steps),
+            Err(msg) => return (StatusCode::BAD_REQUEST, msg).into_response(),
+        }
+    } else {
+        None
+    };
+
+    let mut query = QueryBuilder::<Postgres>::new("UPDATE targets SET ");
+    let mut fields = query.separated(", ");
+    if let Some(url) = url {
+        fields.push("url = ").push_bind_unseparated(url);
+        // A new host may serve a different certificate (or none), so forget the old one and
+        // refresh it on the next check
+        fields.push("cert_expires_at = NULL");
+        fields.push("cert_checked_at = NULL");
+    }
+    if let Some(check_type) = payload.check_type {
+        fields.push("check_type = ").push_bind_unseparated(check_type.as_str());
+    }
+    if let Some(secs) = payload.check_interval_secs {
+        fields.push("check_interval_secs = ").push_bind_unseparated(secs);
+    }
+    if let Some(ms) = payload.timeout_ms {
+        fields.push("timeout_ms = ").push_bind_unseparated(ms);
+    }
+    if let Some(status) = payload.expected_status {
+        fields.push("expected_status = ").push_bind_unseparated(status);
+    }
+    if let Some(headers) = payload.headers {
+        fields.push("headers = ").push_bind_unseparated(headers.map(SqlJson));
+    }
+    if let Some(follow) = payload.follow_redirects {
+        fields.push("follow_redirects = ").push_bind_unseparated(follow);
+    }
+    if let Some(substring) = payload.expected_body_substring {
+        fields.push("expected_body_substring = ").push_bind_unseparated(substring);
+    }
+    if let Some(method) = http_method {
+        fields.push("http_method = ").push_bind_unseparated(method);
+    }
+    if let Some(body) = payload.request_body {
+        fields.push("request_body = ").push_bind_unseparated(body);
+    }
+    if let Some(tags) = payload.tags {
+        fields.push("tags = ").push_bind_unseparated(tags);
+    }
+    if let Some(pattern) = payload.expected_body_regex {
+        fields.push("expected_body_regex = ").push_bind_unseparated(pattern);
+    }
+    if let Some(content_type) = payload.expected_content_type {
+        fields.push("expected_content_type = ").push_bind_unseparated(content_type);
+    }
+    if let Some(encoding) = payload.expected_content_encoding {
+        fields.push("expected_content_encoding = ").push_bind_unseparated(encoding);
+    }
+    if let Some(headers) = payload.expected_headers {
+        fields.push("expected_headers = ").push_bind_unseparated(headers.map(SqlJson));
+    }
+    if let Some(user) = payload.basic_auth_user {
+        fields.push("basic_auth_user = ").push_bind_unseparated(user);
+    }
+    if let Some(pass) = payload.basic_auth_pass {
+        fields.push("basic_auth_pass = ").push_bind_unseparated(pass);
+    }
+    if let Some(ms) = payload.latency_threshold_ms {
+        fields.push("latency_threshold_ms = ").push_bind_unseparated(ms);
+    }
+    if let Some(name) = payload.display_name {
+        fields.push("display_name = ").push_bind_unseparated(name);
+    }
+    if let Some(max) = payload.max_history {
+        fields.push("max_history = ").push_bind_unseparated(max);
+    }
+    if let Some(ranges) = payload.expected_status_ranges {
+        fields.push("expected_status_ranges = ").push_bind_unseparated(ranges);
+    }
+    if let Some(metadata) = payload.metadata {
+        fields.push("metadata = ").push_bind_unseparated(SqlJson(metadata.unwrap_or_default()));
+    }
+    if let Some(insecure) = payload.insecure_skip_tls_verify {
+        fields.push("insecure_skip_tls_verify = ").push_bind_unseparated(insecure);
+    }
+    if let Some(steps) = steps {
+        fields.push("steps = ").push_bind_unseparated(steps.map(SqlJson));
+    }
+    if let Some(schedule) = payload.cron_schedule {
+        fields.push("cron_schedule = ").push_bind_unseparated(schedule);
+    }
+    if let Some(family) = payload.ip_family {
+        fields.push("ip_family = ").push_bind_unseparated(family.as_str());
+    }
+    query.push(" WHERE id = ").push_bind(target_id);
+    query.push(format!(" RETURNING {TARGET_COLUMNS}"));
+
+    match query.build_query_as::<Target>().fetch_optional(&state.pool).await {
+        Ok(Some(target)) => {
+            info!(target_id, "target updated");
+            (StatusCode::OK, Json(target)).into_response()
+        }
+        Ok(None) => (StatusCode::NOT_FOUND, "target not found").into_response(),
+        Err(e) if e.as_database_error().is_some_and(|db| db.is_unique_violation()) => {
+            (StatusCode::CONFLICT, "target with this URL already exists").into_response()
+        }
+        Err(e) => {
+            error!(error = %e, "failed to update target");
+            db_error(&e)
+        }
+    }
+}
+
+/// Settings that are checked on their own, the same way on create, bulk import, and update;
+/// each is `None` when it isn't being set. Normalized values are written back in place.
+struct TargetFields<'a> {
+    check_interval_secs: Option<i32>,
+    timeout_ms: Option<i32>,
+    expected_status: Option<i32>,
+    headers: Option<&'a HashMap<String, String>>,
+    latency_threshold_ms: Option<i32>,
+    max_history: Option<i32>,
+    tags: Option<&'a mut Vec<String>>,
+    expected_body_regex: Option<&'a str>,
+    expected_content_type: Option<&'a mut String>,
+    expected_content_encoding: Option<&'a mut String>,
+    expected_headers: Option<&'a mut HashMap<String, String>>,
+    display_name: Option<&'a mut String>,
+    expected_status_ranges: Option<&'a mut String>,
+    cron_schedule: Option<&'a mut String>,
+}
+
+impl TargetFields<'_> {
+    /// Returns a message suitable for a `400` for the first invalid setting.
+    fn validate(self) -> Result<(), String> {
+        if self.check_interval_secs.is_some_and(|secs| secs <= 0) {
+            return Err("check_interval_secs must be greater than zero".into());
+        }
+        if self.timeout_ms.is_some_and(|ms| ms <= 0) {
+            return Err("timeout_ms must be greater than zero".into());
+        }
+        if self.expected_status.is_some_and(|status| !(100..=599).contains(&status)) {
+            return Err("expected_status must be a valid HTTP status code (100-599)".into());
+        }
+        if let Some(headers) = self.headers {
+            validate_headers(headers)?;
+        }
+        if self.latency_threshold_ms.is_some_and(|ms| ms <= 0) {
+            return Err("latency_threshold_ms must be greater than zero".into());
+        }
+        if self.max_history.is_some_and(|max| max <= 0) {
+            return Err("max_history must be greater than zero".into());
+        }
+        if let Some(tags) = self.tags {
+            *tags = validate_tags(tags)?;
+        }
+        if let Some(pattern) = self.expected_body_regex {
+            validate_body_regex(pattern)?;
+        }
+        if let Some(content_type) = self.expected_content_type {
+            *content_type = validate_content_type(content_type)?;
+        }
+        if let Some(encoding) = self.expected_content_encoding {
+            *encoding = validate_content_encoding(encoding)?;
+        }
+        if let Some(headers) = self.expected_headers {
+            *headers = validate_expected_headers(headers)?;
+        }
+        if let Some(name) = self.display_name {
+            *name = validate_display_name(name)?;
+        }
+        if let Some(ranges) = self.expected_status_ranges {
+            *ranges = validate_status_ranges(ranges)?;
+        }
+        if let Some(schedule) = self.cron_schedule {
+            *schedule = validate_cron_schedule(schedule)?;
+        }
+        Ok(())
+    }
+}
+
+#[instrument(skip(state, headers, body))]
+async fn bulk_create_targets(State(state): State<AppState>, headers: HeaderMap, body: Bytes) -> impl IntoResponse {
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("application/json");
+    let parsed = if content_type.starts_with("text/csv") {
+        parse_csv_targets(&body)
+    } else if content_type.starts_with("application/json") {
+        parse_json_targets(&body)
+    } else {
+        return (StatusCode::UNSUPPORTED_MEDIA_TYPE, "expected application/json or text/csv").into_response();
+    };
+    let rows = match parsed {
+        Ok(rows) => rows,
+        Err(msg) => return (StatusCode::BAD_REQUEST, msg).into_response(),
+    };
+
+    let mut summary = BulkSummary::default();
+    let mut valid = Vec::new();
+    for (row, parsed) in rows {
+        match parsed.and_then(BulkTarget::validated) {
+            Ok(target) => valid.push(target),
+            Err(error) => summary.errors.push(BulkRowError { row, error }),
+        }
+    }
+
+    let result: Result<(), sqlx::Error> = async {
+        let mut tx = state.pool.begin().await?;
+        for target in &valid {
+            let inserted = sqlx::query(
+                r#"
+                INSERT INTO targets (
+                    url, check_type, headers, check_interval_secs, timeout_ms,
+                    expected_status, follow_redirects, expected_body_substring, http_method, request_body, tags,
+                    expected_body_regex, expected_content_type, basic_auth_user, basic_auth_pass, latency_threshold_ms,
+                    display_name, max_history, expected_status_ranges, metadata, insecure_skip_tls_verify, steps,
+                    expected_headers, cron_schedule, ip_family, expected_content_encoding
+                )
+                VALUES (
+                    $1, $2, $3, $4, $5, COALESCE($6, 200), COALESCE($7, true), $8, COALESCE($9, 'GET'), $10,
+                    $11, $12, $13, $14, $15, $16, $17, $18, $19, COALESCE($20, '{}'),
+                    $21, $22, $23, $24, $25, $26
+                )
+                ON CONFLICT DO NOTHING
+                "#,
+            )
+            .bind(&target.url)
+            .bind(target.check_type.as_str())
+            .bind(target.headers.clone().map(SqlJson))
+            .bind(target.check_interval_secs)
+            .bind(target.timeout_ms)
+            .bind(target.expected_status)
+            .bind(target.follow_redirects)
+            .bind(&target.expected_body_substring)
+            .bind(&target.http_method)
+            .bind(&target.request_body)
+            .bind(&target.tags)
+            .bind(&target.expected_body_regex)
+            .bind(&target.expected_content_type)
+            .bind(&target.basic_auth_user)
+            .bind(&target.basic_auth_pass)
+            .bind(target.latency_threshold_ms)
+            .bind(&target.display_name)
+            .bind(target.max_history)
+            .bind(&target.expected_status_ranges)
+            .bind(target.metadata.clone().map(SqlJson))
+            .bind(target.insecure_skip_tls_verify)
+            .bind(target.steps.clone().map(SqlJson))
+            .bind(target.expected_headers.clone().map(SqlJson))
+            .bind(&target.cron_schedule)
+            .bind(target.ip_family.as_str())
+            .bind(&target.expected_content_encoding)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+            if inserted > 0 {
+                summary.inserted += 1;
+            } else {
+                summary.skipped += 1;
+            }
+        }
+        tx.commit().await
+    }
+    .await;
+
+    match result {
+        Ok(()) => {
+            info!(inserted = summary.inserted, skipped = summary.skipped, errors = summary.errors.len(), "bulk import complete");
+            (StatusCode::OK, Json(summary)).into_response()
+        }
+        Err(e) => {
+            error!(error = %e, "bulk import failed");
+            db_error(&e)
+        }
+    }
+}
+
+/// Splits a JSON array into per-row results so one malformed element doesn't reject the batch.
+fn parse_json_targets(body: &[u8]) -> Result<Vec<BulkRow>, String> {
+    let items: Vec<serde_json::Value> =
+        serde_json::from_slice(body).map_err(|e| format!("expected a JSON array of targets: {e}"))?;
+    Ok(items
+        .into_iter()
+        .enumerate()
+        .map(|(i, item)| (i + 1, serde_json::from_value(item).map_err(|e| e.to_string())))
+        .collect())
+}
+
+/// Parses CSV with a header row containing `url` and optionally any of the per-target settings columns.
+fn parse_csv_targets(body: &[u8]) -> Result<Vec<BulkRow>, String> {
+    let mut reader = csv::ReaderBuilder::new().trim(csv::Trim::All).from_reader(body);
+    let has_url = reader
+        .headers()
+        .map_err(|e| format!("invalid CSV header: {e}"))?
+        .iter()
+        .any(|h| h == "url");
+    if !has_url {
+        return Err("CSV header must include a url column".into());
+    }
+    Ok(reader
+        .deserialize::<BulkTarget>()
+        .enumerate()
+        .map(|(i, row)| (i + 1, row.map_err(|e| e.to_string())))
+        .collect())
+}
+
+#[instrument(skip(state))]
+async fn get_target_cert(Path(target_id): Path<i32>, State(state): State<AppState>) -> impl IntoResponse {
+    let row = sqlx::query_scalar::<_, Option<DateTime<Utc>>>(r#"SELECT cert_expires_at FROM targets WHERE id = $1"#)
+        .bind(target_id)
+        .fetch_optional(&state.pool)
+        .await;
+
+    match row {
+        Ok(Some(cert_expires_at)) => {
+            let days_until_expiry = cert_expires_at.map(|at| (at - Utc::now()).num_days());
+            (StatusCode::OK, Json(CertReport { target_id, cert_expires_at, days_until_expiry })).into_response()
+        }
+        Ok(None) => (StatusCode::NOT_FOUND, "target not found").into_response(),
+        Err(e) => {
+            error!(error = %e, "failed to fetch certificate expiry");
+            db_error(&e)
+        }
+    }
+}
+
+#[instrument(skip(state))]
+async fn pause_target(Path(target_id): Path<i32>, State(state): State<AppState>) -> impl IntoResponse {
+    set_target_enabled(&state, target_id, false).await
+}
+
+#[instrument(skip(state))]
+async fn resume_target(Path(target_id): Path<i32>, State(state): State<AppState>) -> impl IntoResponse {
+    set_target_enabled(&state, target_id, true).await
+}
+
+/// Switches the whole service into maintenance mode: the worker stops checking targets and no
+/// alerts are sent until it is switched off again.
+#[instrument(skip(state))]
+async fn service_maintenance_on(State(state): State<AppState>) -> impl IntoResponse {
+    set_service_maintenance(&state, true).await
+}
+
+#[instrument(skip(state))]
+async fn service_maintenance_off(State(state): State<AppState>) -> impl IntoResponse {
+    set_service_maintenance(&state, false).await
+}
+
+/// Flips the maintenance flag in `service_config`, logging when the mode actually changes.
+async fn set_service_maintenance(state: &AppState, on: bool) -> axum::response::Response {
+    let row = sqlx::query_as::<_, (bool, Option<DateTime<Utc>>)>(
+        r#"
+        UPDATE service_config c
+        SET maintenance = $1, maintenance_since = CASE WHEN $1 THEN COALESCE(previous.maintenance_since, NOW()) END
+        FROM (SELECT maintenance, maintenance_since FROM service_config) previous
+        RETURNING previous.maintenance, c.maintenance_since
+        "#,
+    )
+    .bind(on)
+    .fetch_one(&state.pool)
+    .await;
+
+    match row {
+        Ok((was_on, maintenance_since)) => {
+            match (was_on, on) {
+                (false, true) => warn!("service entered maintenance mode, checks and alerts paused"),
+                (true, false) => info!("service left maintenance mode, checks and alerts resumed"),
+                _ => {}
+            }
+            (StatusCode::OK, Json(ServiceMaintenance { maintenance: on, maintenance_since })).into_response()
+        }
+        Err(e) => {
+            error!(error = %e, "failed to update maintenance mode");
+            db_error(&e)
+        }
+    }
+}
+
+/// Whether the service is in maintenance mode.
+async fn service_in_maintenance(pool: &PgPool) -> sqlx::Result<bool> {
+    sqlx::query_scalar::<_, bool>(r#"SELECT COALESCE((SELECT maintenance FROM service_config), false)"#)
+        .fetch_one(pool)
+        .await
+}
+
+/// Flips a target's `enabled` flag, returning the updated target.
+async fn set_target_enabled(state: &AppState, target_id: i32, enabled: bool) -> axum::response::Response {
+    let row = sqlx::query_as::<_, Target>(&format!(
+        "UPDATE targets SET enabled = $2 WHERE id = $1 RETURNING {TARGET_COLUMNS}"
+    ))
+    .bind(target_id)
+    .bind(enabled)
+    .fetch_optional(&state.pool)
+    .await;
+
+    match row {
+        Ok(Some(target)) => {
+            info!(target_id, enabled, "target {}", if enabled { "resumed" } else { "paused" });
+            (StatusCode::OK, Json(target)).into_response()
+        }
+        Ok(None) => (StatusCode::NOT_FOUND, "target not found").into_response(),
+        Err(e) => {
+            error!(error = %e, "failed to update target enabled flag");
+            db_error(&e)
+        }
+    }
+}
+
+#[instrument(skip(state))]
+async fn list_maintenance_windows(Path(target_id): Path<i32>, State(state): State<AppState>) -> impl IntoResponse {
+    let rows = sqlx::query_as::<_, MaintenanceWindow>(
+        r#"
+        SELECT id, target_id, starts_at, ends_at FROM maintenance_windows
+        WHERE target_id = $1
+        ORDER BY starts_at DESC
+        "#,
+    )
+    .bind(target_id)
+    .fetch_all(&state.pool)
+    .await;
+
+    match rows {
+        Ok(rows) => (StatusCode::OK, Json(rows)).into_response(),
+        Err(e) => {
+            error!(error = %e, "failed to fetch maintenance windows");
+            db_error(&e)
+        }
+    }
+}
+
+#[instrument(skip(state))]
+async fn create_maintenance_window(
+    Path(target_id): Path<i32>,
+    State(state): State<AppState>,
+    Json(payload): Json<CreateMaintenanceWindow>,
+) -> impl IntoResponse {
+    if payload.ends_at <= payload.starts_at {
+        return (StatusCode::BAD_REQUEST, "ends_at must be after starts_at").into_response();
+    }
+
+    let row = sqlx::query_as::<_, MaintenanceWindow>(
+        r#"
+        INSERT INTO maintenance_windows (target_id, starts_at, ends_at) VALUES ($1, $2, $3)
+        RETURNING id, target_id, starts_at, ends_at
+        "#,
+    )
+    .bind(target_id)
+    .bind(payload.starts_at)
+    .bind(payload.ends_at)
+    .fetch_one(&state.pool)
+    .await;
+
+    match row {
+        Ok(window) => {
+            info!(target_id, window_id = window.id, "maintenance window created");
+            (StatusCode::CREATED, Json(window)).into_response()
+        }
+        Err(e) if e.as_database_error().is_some_and(|db| db.is_foreign_key_violation()) => {
+            (StatusCode::NOT_FOUND, "target not found").into_response()
+        }
+        Err(e) => {
+            error!(error = %e, "failed to insert maintenance window");
+            db_error(&e)
+        }
+    }
+}
+
+#[instrument(skip(state))]
+async fn delete_maintenance_window(
+    Path((target_id, window_id)): Path<(i32, i32)>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let result = sqlx::query(r#"DELETE FROM maintenance_windows WHERE id = $1 AND target_id = $2"#)
+        .bind(window_id)
+        .bind(target_id)
+        .execute(&state.pool)
+        .await;
+
+    match result {
+        Ok(res) if res.rows_affected() == 0 => (StatusCode::NOT_FOUND, "maintenance window not found").into_response(),
+        Ok(_) => {
+            info!(target_id, window_id, "maintenance window deleted");
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Err(e) => {
+            error!(error = %e, "failed to delete maintenance window");
+            db_error(&e)
+        }
+    }
+}
+
+/// Lists alert routes, oldest first. Behind the API key since webhook URLs are often credentials.
+#[instrument(skip(state))]
+async fn list_alert_routes(State(state): State<AppState>) -> impl IntoResponse {
+    let rows = sqlx::query_as::<_, AlertRoute>(
+        r#"SELECT id, tag, severity, webhook_url, template, email, created_at FROM alert_routes ORDER BY id"#,
+    )
+    .fetch_all(&state.pool)
+    .await;
+
+    match rows {
+        Ok(routes) => (StatusCode::OK, Json(routes)).into_response(),
+        Err(e) => {
+            error!(error = %e, "failed to list alert routes");
+            db_error(&e)
+        }
+    }
+}
+
+#[instrument(skip(state, payload))]
+async fn create_alert_route(State(state): State<AppState>, Json(payload): Json<CreateAlertRoute>) -> impl IntoResponse {
+    let tag = match payload.tag.map(|tag| validate_tags(&[tag])).transpose() {
+        Ok(tag) => tag.and_then(|tags| tags.into_iter().next()),
+        Err(msg) => return (StatusCode::BAD_REQUEST, msg).into_response(),
+    };
+    let severity = payload.severity.map(|severity| severity.trim().to_lowercase());
+    if severity.as_deref() == Some("") {
+        return (StatusCode::BAD_REQUEST, "severity must not be empty").into_response();
+    }
+    if tag.is_none() && severity.is_none() {
+        return (StatusCode::BAD_REQUEST, "at least one of tag or severity is required").into_response();
+    }
+    let email = payload.email.map(|email| email.trim().to_string());
+    match Destination::new(payload.webhook_url.as_deref(), payload.template.as_deref(), email.as_deref()) {
+        Ok(Destination::Email(_)) if !state.alerter.email_enabled() => {
+            return (StatusCode::BAD_REQUEST, "email routes need SMTP_URL, ALERT_FROM, and ALERT_TO to be set").into_response();
+        }
+        Ok(_) => {}
+        Err(e) => return (StatusCode::BAD_REQUEST, format!("{e:#}")).into_response(),
+    }
+
+    let row = sqlx::query_as::<_, AlertRoute>(
+        r#"
+        INSERT INTO alert_routes (tag, severity, webhook_url, template, email) VALUES ($1, $2, $3, $4, $5)
+        RETURNING id, tag, severity, webhook_url, template, email, created_at
+        "#,
+    )
+    .bind(tag)
+    .bind(severity)
+    .bind(payload.webhook_url)
+    .bind(payload.template)
+    .bind(email)
+    .fetch_one(&state.pool)
+    .await;
+
+    match row {
+        Ok(route) => {
+            info!(route_id = route.id, tag = ?route.tag, severity = ?route.severity, "alert route created");
+            (StatusCode::CREATED, Json(route)).into_response()
+        }
+        Err(e) => {
+            error!(error = %e, "failed to insert alert route");
+            db_error(&e)
+        }
+    }
+}
+
+#[instrument(skip(state))]
+async fn delete_alert_route(Path(route_id): Path<i32>, State(state): State<AppState>) -> impl IntoResponse {
+    let result = sqlx::query(r#"DELETE FROM alert_routes WHERE id = $1"#)
+        .bind(route_id)
+        .execute(&state.pool)
+        .await;
+
+    match result {
+        Ok(res) if res.rows_affected() == 0 => (StatusCode::NOT_FOUND, "alert route not found").into_response(),
+        Ok(_) => {
+            info!(route_id, "alert route deleted");
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Err(e) => {
+            error!(error = %e, "failed to delete alert route");
+            db_error(&e)
+        }
+    }
+}
+
+/// Runs a check right away, e.g. to confirm a fix without waiting for the next tick.
+#[instrument(skip(state))]
+async fn check_target_now(Path(target_id): Path<i32>, State(state): State<AppState>) -> impl IntoResponse {
+    let target = match fetch_target(&state.pool, target_id).await {
+        Ok(Some(target)) => target,
+        Ok(None) => return (StatusCode::NOT_FOUND, "target not found").into_response(),
+        Err(e) => {
+            error!(error = %e, "failed to fetch target");
+            return db_error(&e);
+        }
+    };
+
+    match run_check(&state, &target).await {
+        // No status means the request itself failed; the failed check is still recorded
+        Ok(record) if record.status_code.is_none() => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({ "error": "target could not be reached", "record": record })),
+        )
+            .into_response(),
+        Ok(record) => (StatusCode::OK, Json(record)).into_response(),
+        Err(e) => {
+            error!(error = %e, "failed to record on-demand check");
+            db_error(e.as_ref())
+        }
+    }
+}
+
+/// Records a deploy reported by CI and checks the target right away in the background. The body
+/// (any JSON, or empty) must be signed with `DEPLOY_WEBHOOK_SECRET` in `X-Signature`; this route
+/// takes no API key, so CI only needs the shared secret.
+#[instrument(skip(state, headers, body))]
+async fn record_deploy(
+    Path(target_id): Path<i32>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> impl IntoResponse {
+    let signature = headers.get(SIGNATURE_HEADER).and_then(|v| v.to_str().ok());
+    if !signature.is_some_and(|signature| state.deploy_secret.verify(&body, signature)) {
+        warn!(target_id, "rejected deploy notification with missing or invalid signature");
+        return (StatusCode::UNAUTHORIZED, "missing or invalid X-Signature").into_response();
+    }
+    let details = if body.iter().all(u8::is_ascii_whitespace) {
+        None
+    } else {
+        match serde_json::from_slice::<serde_json::Value>(&body) {
+            Ok(details) => Some(SqlJson(details)),
+            Err(e) => return (StatusCode::BAD_REQUEST, format!("invalid JSON body: {e}")).into_response(),
+        }
+    };
+
+    let target = match fetch_target(&state.pool, target_id).await {
+        Ok(Some(target)) => target,
+        Ok(None) => return (StatusCode::NOT_FOUND, "target not found").into_response(),
+        Err(e) => {
+            error!(error = %e, "failed to fetch target");
+            return db_error(&e);
+        }
+    };
+
+    let deploy = sqlx::query_as::<_, Deploy>(
+        r#"
+        INSERT INTO deploys (target_id, details) VALUES ($1, $2)
+        RETURNING id, target_id, deployed_at, details
+        "#,
+    )
+    .bind(target_id)
+    .bind(details)
+    .fetch_one(&state.pool)
+    .await;
+
+    match deploy {
+        Ok(deploy) => {
+            info!(target_id, deploy_id = deploy.id, "deploy recorded, checking target");
+            tokio::spawn(async move {
+                if let Err(e) = run_check(&state, &target).await {
+                    error!(target_id = target.id, error = %e, "failed to record post-deploy check");
+                }
+            });
+            (StatusCode::ACCEPTED, Json(deploy)).into_response()
+        }
+        Err(e) => {
+            error!(error = %e, "failed to record deploy");
+            db_error(&e)
+        }
+    }
+}
+
+/// A target's recorded deploys, newest first (`limit` defaults to 50, at most 500).
+#[instrument(skip(state))]
+async fn list_deploys(
+    Path(target_id): Path<i32>,
+    Query(query): Query<LimitQuery>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_LIMIT);
+    if !(1..=MAX_PAGE_LIMIT).contains(&limit) {
+        return (StatusCode::BAD_REQUEST, format!("limit must be between 1 and {MAX_PAGE_LIMIT}")).into_response();
+    }
+
+    let deploys = sqlx::query_as::<_, Deploy>(
+        r#"
+        SELECT id, target_id, deployed_at, details
+        FROM deploys
+        WHERE target_id = $1
+        ORDER BY deployed_at DESC, id DESC
+        LIMIT $2
+        "#,
+    )
+    .bind(target_id)
+    .bind(limit)
+    .fetch_all(&state.pool)
+    .await;
+
+    match deploys {
+        Ok(deploys) => (StatusCode::OK, Json(deploys)).into_response(),
+        Err(e) => {
+            error!(error = %e, "failed to fetch deploys");
+            db_error(&e)
+        }
+    }
+}
+
+/// Starts checking every enabled target in the background, e.g. after a suspected network
+/// incident, and answers `202` with the sweep's job id straight away. Only one sweep runs at a
+/// time; its progress is available from `GET /api/check-all/:job_id`.
+#[instrument(skip(state))]
+async fn check_all(State(state): State<AppState>) -> impl IntoResponse {
+    let targets = match sqlx::query_as::<_, Target>(&format!("SELECT {TARGET_COLUMNS} FROM targets WHERE enabled"))
+        .fetch_all(&state.pool)
+        .await
+    {
+        Ok(targets) => targets,
+        Err(e) => {
+            error!(error = %e, "failed to fetch targets");
+            return db_error(&e);
+        }
+    };
+
+    let sweep = {
+        let mut current = state.sweep.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(running) = current.as_ref().filter(|sweep| sweep.finished_at.is_none()) {
+            return (StatusCode::CONFLICT, Json(running.clone())).into_response();
+        }
+        let sweep = Sweep {
+            job_id: Uuid::new_v4().to_string(),
+            started_at: Utc::now(),
+            finished_at: None,
+            total: targets.len(),
+            succeeded: 0,
+            failed: 0,
+        };
+        *current = Some(sweep.clone());
+        sweep
+    };
+    info!(job_id = %sweep.job_id, targets = sweep.total, "manual sweep started");
+
+    tokio::spawn(run_sweep(state, targets));
+    (StatusCode::ACCEPTED, Json(sweep)).into_response()
+}
+
+/// Checks `targets` with the worker's concurrency, tallying results into the current sweep.
+async fn run_sweep(state: AppState, targets: Vec<Target>) {
+    let state = &state;
+    let slots = &Semaphore::new(state.worker.concurrency);
+    let tally = &|healthy: bool| {
+        let mut sweep = state.sweep.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(sweep) = sweep.as_mut() {
+            if healthy {
+                sweep.succeeded += 1;
+            } else {
+                sweep.failed += 1;
+            }
+        }
+    };
+
+    stream::iter(&targets)
+        .for_each_concurrent(None, |t| async move {
+            let healthy = match run_limited_check(state, t, slots).await {
+                Ok(record) => is_check_healthy(
+                    record.status_code,
+                    t.expected_status,
+                    t.expected_status_ranges.as_ref(),
+                    &[record.body_match, record.content_type_match, record.headers_match, record.encoding_match],
+                ),
+                Err(e) => {
+                    error!(target_id = t.id, error = %e, "failed to record health check");
+                    false
+                }
+            };
+            tally(healthy);
+        })
+        .await;
+
+    let mut sweep = state.sweep.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some(sweep) = sweep.as_mut() {
+        sweep.finished_at = Some(Utc::now());
+        info!(job_id = %sweep.job_id, succeeded = sweep.succeeded, failed = sweep.failed, "manual sweep finished");
+    }
+}
+
+/// Alerts sent, newest first, optionally for one target (`limit` defaults to 50, at most 500).
+#[instrument(skip(state))]
+async fn list_alerts(Query(query): Query<AlertQuery>, State(state): State<AppState>) -> impl IntoResponse {
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_LIMIT);
+    if !(1..=MAX_PAGE_LIMIT).contains(&limit) {
+        return (StatusCode::BAD_REQUEST, format!("limit must be between 1 and {MAX_PAGE_LIMIT}")).into_response();
+    }
+
+    let alerts = sqlx::query_as::<_, AlertRecord>(
+        r#"
+        SELECT id, target_id, kind, channel, sent_at, delivery_ok
+        FROM alerts
+        WHERE $1::int IS NULL OR target_id = $1
+        ORDER BY sent_at DESC, id DESC
+        LIMIT $2
+        "#,
+    )
+    .bind(query.target_id)
+    .bind(limit)
+    .fetch_all(&state.pool)
+    .await;
+
+    match alerts {
+        Ok(alerts) => (StatusCode::OK, Json(alerts)).into_response(),
+        Err(e) => {
+            error!(error = %e, "failed to fetch alerts");
+            db_error(&e)
+        }
+    }
+}
+
+/// Progress of a manual sweep; only the latest sweep can be looked up.
+#[instrument(skip(state))]
+async fn get_sweep(Path(job_id): Path<String>, State(state): State<AppState>) -> impl IntoResponse {
+    let sweep = state.sweep.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    match sweep.as_ref().filter(|sweep| sweep.job_id == job_id) {
+        Some(sweep) => (StatusCode::OK, Json(sweep.clone())).into_response(),
+        None => (StatusCode::NOT_FOUND, "sweep not found").into_response(),
+    }
+}
+
+/// Delete a target and its history
+#[utoipa::path(
+    delete,
+    path = "/api/targets/{target_id}",
+    params(("target_id" = i32, Path, description = "Target id")),
+    responses(
+        (status = 204, description = "Target deleted"),
+        (status = 404, description = "Target not found", body = String),
+    ),
+    security(("api_key" = [])),
+)]
+#[instrument(skip(state))]
+async fn delete_target(Path(target_id): Path<i32>, State(state): State<AppState>) -> impl IntoResponse {
+    let result = sqlx::query(r#"DELETE FROM targets WHERE id = $1"#)
+        .bind(target_id)
+        .execute(&state.pool)
+        .await;
+
+    match result {
+        Ok(res) if res.rows_affected() == 0 => (StatusCode::NOT_FOUND, "target not found").into_response(),
+        Ok(_) => {
+            info!(target_id, "target deleted");
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Err(e) => {
+            error!(error = %e, "failed to delete target");
+            db_error(&e)
+        }
+    }
+}
+
+/// Deletes a target's check history, keeping the target itself, and resets the state derived
+/// from that history (failure streak, latency average, and slow and burn-rate flags).
+#[instrument(skip(state))]
+async fn clear_history(Path(target_id): Path<i32>, State(state): State<AppState>) -> impl IntoResponse {
+    // The target row selects what to delete, so a missing target yields no row at all
+    let result = sqlx::query_scalar::<_, i64>(
+        r#"
+        WITH target AS (
+            UPDATE targets SET consecutive_failures = 0, latency_ema_ms = NULL, slow = false, burning = false
+            WHERE id = $1
+            RETURNING id
+        ),
+        deleted AS (
+            DELETE FROM health_checks WHERE target_id IN (SELECT id FROM target) RETURNING 1
+        )
+        SELECT (SELECT COUNT(*) FROM deleted) FROM target
+        "#,
+    )
+    .bind(target_id)
+    .fetch_optional(&state.pool)
+    .await;
+
+    match result {
+        Ok(Some(deleted)) => {
+            info!(target_id, deleted, "target history cleared");
+            (StatusCode::OK, Json(json!({ "deleted": deleted }))).into_response()
+        }
+        Ok(None) => (StatusCode::NOT_FOUND, "target not found").into_response(),
+        Err(e) => {
+            error!(error = %e, "failed to clear target history");
+            db_error(&e)
+        }
+    }
+}
+
+/// Imports historical checks for a target from a JSON array or CSV body, keeping each supplied
+/// `checked_at`. Valid rows are inserted together and invalid ones reported, like the bulk target
+/// import; a row matching an existing check's timestamp is skipped so re-running an import is safe.
+#[instrument(skip(state, headers, body))]
+async fn import_history(
+    Path(target_id): Path<i32>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> impl IntoResponse {
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("application/json");
+    let parsed = if content_type.starts_with("text/csv") {
+        parse_csv_checks(&body)
+    } else if content_type.starts_with("application/json") {
+        parse_json_checks(&body)
+    } else {
+        return (StatusCode::UNSUPPORTED_MEDIA_TYPE, "expected application/json or text/csv").into_response();
+    };
+    let rows = match parsed {
+        Ok(rows) => rows,
+        Err(msg) => return (StatusCode::BAD_REQUEST, msg).into_response(),
+    };
+
+    match fetch_target(&state.pool, target_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return (StatusCode::NOT_FOUND, "target not found").into_response(),
+        Err(e) => {
+            error!(error = %e, "failed to fetch target");
+            return db_error(&e);
+        }
+    }
+
+    let mut summary = BulkSummary::default();
+    let (mut checked_at, mut status_codes, mut response_times) = (Vec::new(), Vec::new(), Vec::new());
+    for (row, parsed) in rows {
+        match parsed.and_then(ImportedCheck::validated) {
+            Ok(check) => {
+                checked_at.push(check.checked_at);
+                status_codes.push(check.status_code);
+                response_times.push(check.response_time_ms);
+            }
+            Err(error) => summary.errors.push(BulkRowError { row, error }),
+        }
+    }
+
+    // A single statement, so the import is all-or-nothing
+    let inserted = sqlx::query(
+        r#"
+        INSERT INTO health_checks (target_id, checked_at, status_code, response_time_ms)
+        SELECT DISTINCT ON (i.checked_at) $1, i.checked_at, i.status_code, i.response_time_ms
+        FROM UNNEST($2::timestamptz[], $3::int[], $4::int[]) AS i(checked_at, status_code, response_time_ms)
+        WHERE NOT EXISTS (
+            SELECT 1 FROM health_checks h WHERE h.target_id = $1 AND h.checked_at = i.checked_at
+        )
+        "#,
+    )
+    .bind(target_id)
+    .bind(&checked_at)
+    .bind(&status_codes)
+    .bind(&response_times)
+    .execute(&state.pool)
+    .await;
+
+    match inserted {
+        Ok(result) => {
+            summary.inserted = result.rows_affected();
+            summary.skipped = checked_at.len() as u64 - summary.inserted;
+            info!(target_id, inserted = summary.inserted, skipped = summary.skipped, errors = summary.errors.len(), "history import complete");
+            (StatusCode::OK, Json(summary)).into_response()
+        }
+        Err(e) => {
+            error!(error = %e, "history import failed");
+            db_error(&e)
+        }
+    }
+}
+
+/// Splits a JSON array into per-row results, like [`parse_json_targets`].
+fn parse_json_checks(body: &[u8]) -> Result<Vec<ImportRow>, String> {
+    let items: Vec<serde_json::Value> =
+        serde_json::from_slice(body).map_err(|e| format!("expected a JSON array of checks: {e}"))?;
+    Ok(items
+        .into_iter()
+        .enumerate()
+        .map(|(i, item)| (i + 1, serde_json::from_value(item).map_err(|e| e.to_string())))
+        .collect())
+}
+
+/// Parses CSV with a header row containing `checked_at` and optionally `status_code` and `response_time_ms`.
+fn parse_csv_checks(body: &[u8]) -> Result<Vec<ImportRow>, String> {
+    let mut reader = csv::ReaderBuilder::new().trim(csv::Trim::All).from_reader(body);
+    let has_checked_at = reader
+        .headers()
+        .map_err(|e| format!("invalid CSV header: {e}"))?
+        .iter()
+        .any(|h| h == "checked_at");
+    if !has_checked_at {
+        return Err("CSV header must include a checked_at column".into());
+    }
+    Ok(reader
+        .deserialize::<ImportedCheck>()
+        .enumerate()
+        .map(|(i, row)| (i + 1, row.map_err(|e| e.to_string())))
+        .collect())
+}
+
+/// WebSocket feed pushing every new health check as a JSON message.
+async fn live_feed(ws: WebSocketUpgrade, State(state): State<AppState>) -> impl IntoResponse {
+    let updates = state.live.subscribe();
+    ws.on_upgrade(move |socket| stream_live(socket, updates))
+}
+
+/// Forwards broadcast checks to one client until it disconnects or falls too far behind.
+async fn stream_live(mut socket: WebSocket, mut updates: broadcast::Receiver<LiveUpdate>) {
+    loop {
+        tokio::select! {
+            update = updates.recv() => match update {
+                Ok(update) => {
+                    if socket.send(Message::Text(update.message)).await.is_err() {
+                        break;
+                    }
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    // Drop slow clients instead of buffering for them; they can reconnect
+                    warn!(skipped, "live subscriber lagging, closing connection");
+                    let frame = CloseFrame { code: close_code::AGAIN, reason: "subscriber lagging".into() };
+                    let _ = socket.send(Message::Close(Some(frame))).await;
+                    break;
+                }
+                Err(RecvError::Closed) => break,
+            },
+            incoming = socket.recv() => match incoming {
+                // Client messages are ignored; pings are answered automatically
+                Some(Ok(Message::Close(_)) | Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+/// Server-sent `check` events for every recorded check, or only those of `target_id`. Lagging
+/// subscribers are disconnected like on `/api/live`; `EventSource` clients reconnect on their own.
+#[instrument(skip(state))]
+async fn event_stream(Query(query): Query<EventsQuery>, State(state): State<AppState>) -> impl IntoResponse {
+    let target_id = query.target_id;
+    let events = stream::unfold(state.live.subscribe(), move |mut updates| async move {
+        loop {
+            match updates.recv().await {
+                Ok(update) if target_id.is_none_or(|id| id == update.target_id) => {
+                    let event = Event::default().event("check").data(update.message);
+                    return Some((Ok::<_, Infallible>(event), updates));
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!(skipped, "event subscriber lagging, closing stream");
+                    return None;
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+    Sse::new(events).keep_alive(KeepAlive::new().interval(SSE_KEEP_ALIVE_INTERVAL))
+}
+
+/// Liveness probe for the monitor itself: `200` as long as the database answers a trivial query.
+/// A stale worker is reported in the body but doesn't fail the probe; `/ready` covers that.
+async fn health(State(state): State<AppState>) -> impl IntoResponse {
+    let db_up = match sqlx::query_scalar::<_, i32>("SELECT 1").fetch_one(&state.pool).await {
+        Ok(_) => true,
+        Err(e) => {
+            error!(error = %e, "health check query failed");
+            false
+        }
+    };
+    let worker_stale = state.worker_report().stale;
+
+    let (code, status) = if db_up {
+        (StatusCode::OK, "ok")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "degraded")
+    };
+    let body = json!({
+        "status": status,
+        "db": if db_up { "up" } else { "down" },
+        "worker": if worker_stale { "stale" } else { "ok" },
+    });
+    (code, Json(body))
+}
+
+/// Readiness probe: `503` until startup has finished (migrations, seeding, and the first worker
+/// tick), and again whenever the worker goes stale.
+async fn ready(State(state): State<AppState>) -> impl IntoResponse {
+    let started = state.ready.load(Ordering::Relaxed);
+    let worker_stale = state.worker_report().stale;
+    if started && worker_stale {
+        warn!(stale_after_secs = state.worker.stale_after().as_secs(), "background worker has not completed a tick recently");
+    }
+
+    let (code, status) = match (started, worker_stale) {
+        (false, _) => (StatusCode::SERVICE_UNAVAILABLE, "starting"),
+        (true, true) => (StatusCode::SERVICE_UNAVAILABLE, "worker stale"),
+        (true, false) => (StatusCode::OK, "ready"),
+    };
+    (code, Json(json!({ "status": status })))
+}
+
+/// When the background worker last completed a tick, and whether it looks stuck.
+async fn get_worker_status(State(state): State<AppState>) -> impl IntoResponse {
+    Json(state.worker_report())
+}
+
+/// Check throughput and error counts since startup.
+async fn get_worker_stats(State(state): State<AppState>) -> impl IntoResponse {
+    Json(state.stats_report())
+}
+
+/// The worker's database round-trip samples over `window` (default `1h`), for charting.
+#[instrument(skip(state))]
+async fn get_db_latency(Query(query): Query<WindowQuery>, State(state): State<AppState>) -> impl IntoResponse {
+    let window_secs = match parse_window(query.window.as_deref().unwrap_or("1h")) {
+        Ok(secs) => secs,
+        Err(msg) => return (StatusCode::BAD_REQUEST, msg).into_response(),
+    };
+
+    let samples = sqlx::query_as::<_, DbLatencySample>(
+        r#"
+        SELECT checked_at, latency_ms, region
+        FROM db_latency_samples
+        WHERE checked_at >= NOW() - ($1 * INTERVAL '1 second')
+        ORDER BY checked_at
+        "#,
+    )
+    .bind(window_secs)
+    .fetch_all(&state.pool)
+    .await;
+
+    match samples {
+        Ok(samples) => (StatusCode::OK, Json(DbLatencyReport { window_secs, samples })).into_response(),
+        Err(e) => {
+            error!(error = %e, "failed to fetch database latency samples");
+            db_error(&e)
+        }
+    }
+}
+
+/// Prometheus scrape endpoint in the text exposition format.
+async fn prometheus_metrics(State(state): State<AppState>) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        state.metrics.render(),
+    )
+}
+
+/// Checks that `raw` is an absolute HTTP(S) URL, returning a message suitable for a `400` otherwise.
+fn validate_url(raw: &str) -> Result<String, String> {
+    let raw = raw.trim();
+    if raw.is_empty() {
+        return Err("url must not be empty".into());
+    }
+    let parsed = reqwest::Url::parse(raw).map_err(|e| format!("invalid url '{raw}': {e}"))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(format!("invalid url '{raw}': scheme must be http or https"));
+    }
+    if parsed.host_str().is_none() {
+        return Err(format!("invalid url '{raw}': missing host"));
+    }
+    Ok(raw.to_string())
+}
+
+#[instrument(skip(state))]
+async fn get_uptime(
+    Path(target_id): Path<i32>,
+    Query(query): Query<UptimeQuery>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let window_secs = match parse_window(query.window.as_deref().unwrap_or("24h")) {
+        Ok(secs) => secs,
+        Err(msg) => return (StatusCode::BAD_REQUEST, msg).into_response(),
+    };
+
+    let uptime = fetch_uptime(
+        &state.pool,
+        target_id,
+        window_secs,
+        query.exclude_maintenance,
+        query.region.as_deref(),
+        query.timeout_as,
+    )
+    .await;
+    match uptime {
+        Ok(report) => (StatusCode::OK, Json(report)).into_response(),
+        Err(e) => {
+            error!(error = %e, "failed to compute uptime");
+            db_error(&e)
+        }
+    }
+}
+
+/// Share of healthy checks for a target over the last `window_secs`, with the failed checks split
+/// into `degraded` timeouts and `down` according to `timeout_as`.
+async fn fetch_uptime(
+    pool: &PgPool,
+    target_id: i32,
+    window_secs: i64,
+    exclude_maintenance: bool,
+    region: Option<&str>,
+    timeout_as: TimeoutAs,
+) -> sqlx::Result<UptimeReport> {
+    // Timed-out checks have no status, so they are never among the `up` ones
+    let (checks, up, degraded) = sqlx::query_as::<_, (i64, i64, i64)>(
+        r#"
+        SELECT
+            COUNT(*),
+            COUNT(*) FILTER (WHERE check_healthy(h, t)),
+            COUNT(*) FILTER (WHERE $5 AND h.error_kind = 'timeout')
+        FROM health_checks h
+        JOIN targets t ON t.id = h.target_id
+        WHERE h.target_id = $1 AND h.checked_at >= NOW() - ($2 * INTERVAL '1 second')
+          AND NOT ($3 AND EXISTS (
+              SELECT 1 FROM maintenance_windows m
+              WHERE m.target_id = h.target_id AND h.checked_at >= m.starts_at AND h.checked_at < m.ends_at
+          ))
+          AND ($4::text IS NULL OR h.region = $4)
+        "#
+    )
+    .bind(target_id)
+    .bind(window_secs)
+    .bind(exclude_maintenance)
+    .bind(region)
+    .bind(timeout_as == TimeoutAs::Degraded)
+    .fetch_one(pool)
+    .await?;
+
+    let uptime_pct = (checks > 0).then(|| percentage(up, checks));
+    let down = checks - up - degraded;
+    Ok(UptimeReport { target_id, window_secs, checks, up, degraded, down, uptime_pct })
+}
+
+/// `up` as a percentage of `checks`, rounded to two decimals.
+fn percentage(up: i64, checks: i64) -> f64 {
+    (up as f64 * 10_000.0 / checks as f64).round() / 100.0
+}
+
+/// Aggregate uptime of every target tagged `tag`, such as a team's services for an SLA, counted
+/// like the per-target uptime.
+#[instrument(skip(state))]
+async fn get_tag_uptime(Query(query): Query<TagUptimeQuery>, State(state): State<AppState>) -> impl IntoResponse {
+    let window_secs = match parse_window(query.window.as_deref().unwrap_or("24h")) {
+        Ok(secs) => secs,
+        Err(msg) => return (StatusCode::BAD_REQUEST, msg).into_response(),
+    };
+
+    let rows = sqlx::query_as::<_, (i32, i64, i64)>(
+        r#"
+        SELECT
+            t.id,
+            COUNT(*),
+            COUNT(*) FILTER (WHERE check_healthy(h, t))
+        FROM targets t
+        JOIN health_checks h ON h.target_id = t.id
+        WHERE t.tags @> ARRAY[$1] AND h.checked_at >= NOW() - ($2 * INTERVAL '1 second')
+          AND NOT ($3 AND EXISTS (
+              SELECT 1 FROM maintenance_windows m
+              WHERE m.target_id = h.target_id AND h.checked_at >= m.starts_at AND h.checked_at < m.ends_at
+          ))
+        GROUP BY t.id
+        ORDER BY t.id
+        "#,
+    )
+    .bind(&query.tag)
+    .bind(window_secs)
+    .bind(query.exclude_maintenance)
+    .fetch_all(&state.pool)
+    .await;
+    let rows = match rows {
+        Ok(rows) => rows,
+        Err(e) => {
+            error!(error = %e, "failed to compute tag uptime");
+            return db_error(&e);
+        }
+    };
+
+    let checks = rows.iter().map(|(_, checks, _)| checks).sum::<i64>();
+    let up = rows.iter().map(|(_, _, up)| up).sum::<i64>();
+    let per_target: Vec<(i32, f64)> = rows.iter().map(|&(id, checks, up)| (id, percentage(up, checks))).collect();
+    let uptime_pct = match query.mode {
+        _ if per_target.is_empty() => None,
+        UptimeMode::Pooled => Some(percentage(up, checks)),
+        UptimeMode::Average => {
+            let mean = per_target.iter().map(|(_, pct)| pct).sum::<f64>() / per_target.len() as f64;
+            Some((mean * 100.0).round() / 100.0)
+        }
+    };
+    // Rows are ordered by id, so the first of several equally bad targets wins
+    let worst = per_target.iter().copied().reduce(|worst, next| if next.1 < worst.1 { next } else { worst });
+
+    let report = TagUptimeReport {
+        tag: query.tag,
+        window_secs,
+        mode: query.mode,
+        targets: per_target.len(),
+        checks,
+        up,
+        uptime_pct,
+        worst_target_id: worst.map(|(id, _)| id),
+        worst_uptime_pct: worst.map(|(_, pct)| pct),
+    };
+    (StatusCode::OK, Json(report)).into_response()
+}
+
+/// Collapses runs of consecutive failed checks in the window into incidents, newest first.
+#[instrument(skip(state))]
+async fn get_incidents(
+    Path(target_id): Path<i32>,
+    Query(query): Query<WindowQuery>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let window_secs = match parse_window(query.window.as_deref().unwrap_or("7d")) {
+        Ok(secs) => secs,
+        Err(msg) => return (StatusCode::BAD_REQUEST, msg).into_response(),
+    };
+
+    // Gaps and islands: the difference between the overall row number and the row number within
+    // the same failed/healthy partition is constant along each run.
+    let rows = sqlx::query_as::<_, IncidentRow>(
+        r#"
+        WITH checks AS (
+            SELECT
+                h.checked_at,
+                h.error_kind,
+                NOT check_healthy(h, t) AS failed
+            FROM health_checks h
+            JOIN targets t ON t.id = h.target_id
+            WHERE h.target_id = $1 AND h.checked_at >= NOW() - ($2 * INTERVAL '1 second')
+        ),
+        islands AS (
+            SELECT
+                checked_at,
+                error_kind,
+                failed,
+                LEAD(checked_at) OVER (ORDER BY checked_at) AS next_checked_at,
+                ROW_NUMBER() OVER (ORDER BY checked_at)
+                    - ROW_NUMBER() OVER (PARTITION BY failed ORDER BY checked_at) AS island
+            FROM checks
+        )
+        SELECT
+            MIN(checked_at) AS started_at,
+            (ARRAY_AGG(next_checked_at ORDER BY checked_at DESC))[1] AS ended_at,
+            (ARRAY_AGG(error_kind ORDER BY checked_at))[1] AS error_kind
+        FROM islands
+        WHERE failed
+        GROUP BY island
+        ORDER BY started_at DESC
+        "#
+    )
+    .bind(target_id)
+    .bind(window_secs)
+    .fetch_all(&state.pool)
+    .await;
+
+    match rows {
+        Ok(rows) => {
+            let incidents: Vec<Incident> = rows.into_iter().map(Incident::from).collect();
+            (StatusCode::OK, Json(incidents)).into_response()
+        }
+        Err(e) => {
+            error!(error = %e, "failed to compute incidents");
+            db_error(&e)
+        }
+    }
+}
+
+/// Rolls the latest check of every enabled target into one status. Targets that haven't been
+/// checked yet are left out.
+#[instrument(skip(state))]
+async fn get_overall_status(State(state): State<AppState>) -> impl IntoResponse {
+    let row = sqlx::query_as::<_, (i64, i64, Option<DateTime<Utc>>, bool)>(&format!(
+        r#"
+        SELECT
+            COUNT(*),
+            COUNT(*) FILTER (WHERE latest.healthy),
+            MAX(run.status_since),
+            COALESCE((SELECT maintenance FROM service_config), false)
+        FROM targets t
+        JOIN LATERAL (
+            SELECT check_healthy(h, t) AS healthy
+            FROM health_checks h
+            WHERE h.target_id = t.id
+            ORDER BY h.checked_at DESC
+            LIMIT 1
+        ) latest ON true
+        {STATUS_SINCE_LATERAL}
+        WHERE t.enabled
+        "#
+    ))
+    .fetch_one(&state.pool)
+    .await;
+
+    match row {
+        Ok((total, healthy, status_since, maintenance)) => {
+            (StatusCode::OK, Json(OverallStatus::new(total, healthy, status_since, maintenance))).into_response()
+        }
+        Err(e) => {
+            error!(error = %e, "failed to compute overall status");
+            db_error(&e)
+        }
+    }
+}
+
+/// Public status page feed over the latest check of every enabled target. Targets that haven't
+/// been checked yet are left out.
+#[instrument(skip(state))]
+async fn get_public_status(State(state): State<AppState>) -> impl IntoResponse {
+    let rows = sqlx::query_as::<_, PublicStatusRow>(
+        r#"
+        SELECT
+            t.url, t.display_name, t.slow, latest.healthy
+        FROM targets t
+        JOIN LATERAL (
+            SELECT check_healthy(h, t) AS healthy
+            FROM health_checks h
+            WHERE h.target_id = t.id
+            ORDER BY h.checked_at DESC
+            LIMIT 1
+        ) latest ON true
+        WHERE t.enabled
+        ORDER BY COALESCE(t.display_name, t.url)
+        "#,
+    )
+    .fetch_all(&state.pool)
+    .await;
+
+    match rows {
+        Ok(rows) => {
+            let targets = rows.into_iter().map(PublicTargetStatus::from).collect();
+            (StatusCode::OK, Json(PublicStatus::new(targets))).into_response()
+        }
+        Err(e) => {
+            error!(error = %e, "failed to compute public status");
+            db_error(&e)
+        }
+    }
+}
+
+/// Every target with its latest check and 24h uptime, fetched in one round-trip.
+#[instrument(skip(state))]
+async fn get_dashboard(Query(query): Query<DashboardQuery>, State(state): State<AppState>) -> impl IntoResponse {
+    let rows = sqlx::query_as::<_, DashboardRow>(&format!(
+        r#"
+        SELECT
+            t.id, t.url, t.check_type, t.expected_status, t.expected_status_ranges, t.enabled, t.tags, t.consecutive_failures, t.slow,
+            latest.checked_at AS last_checked_at,
+            latest.status_code AS last_status_code,
+            latest.response_time_ms AS last_response_time_ms,
+            latest.body_match AS last_body_match,
+            latest.content_type_match AS last_content_type_match,
+            latest.headers_match AS last_headers_match,
+            latest.encoding_match AS last_encoding_match,
+            ROUND(t.latency_ema_ms::numeric, 2)::float8 AS latency_ema_ms,
+            run.status_since,
+            day.checks AS checks_24h,
+            ROUND(100.0 * day.up / NULLIF(day.checks, 0), 2)::float8 AS uptime_pct_24h
+        FROM targets t
+        LEFT JOIN LATERAL (
+            SELECT checked_at, status_code, response_time_ms, body_match, content_type_match, headers_match, encoding_match
+            FROM health_checks
+            WHERE target_id = t.id
+            ORDER BY checked_at DESC
+            LIMIT 1
+        ) latest ON true
+        CROSS JOIN LATERAL (
+            SELECT
+                COUNT(*) AS checks,
+                COUNT(*) FILTER (WHERE check_healthy(h, t)) AS up
+            FROM health_checks h
+            WHERE h.target_id = t.id AND h.checked_at >= NOW() - INTERVAL '24 hours'
+        ) day
+        {STATUS_SINCE_LATERAL}
+        WHERE $1::text IS NULL OR t.tags @> ARRAY[$1]
+        ORDER BY t.id
+        "#
+    ))
+    .bind(query.tag.as_deref())
+    .fetch_all(&state.pool)
+    .await;
+
+    let regions = sqlx::query_as::<_, RegionStatus>(
+        r#"
+        SELECT DISTINCT ON (h.target_id, h.region)
+            h.target_id, h.region, h.checked_at AS last_checked_at, h.status_code AS last_status_code,
+            check_healthy(h, t) AS healthy
+        FROM health_checks h
+        JOIN targets t ON t.id = h.target_id
+        WHERE h.checked_at >= NOW() - INTERVAL '24 hours'
+          AND ($1::text IS NULL OR t.tags @> ARRAY[$1])
+        ORDER BY h.target_id, h.region, h.checked_at DESC
+        "#
+    )
+    .bind(query.tag.as_deref())
+    .fetch_all(&state.pool)
+    .await;
+
+    match rows.and_then(|rows| regions.map(|regions| (rows, regions))) {
+        Ok((rows, regions)) => {
+            let mut by_target: HashMap<i32, Vec<RegionStatus>> = HashMap::new();
+            for region in regions {
+                by_target.entry(region.target_id).or_default().push(region);
+            }
+            let entries: Vec<DashboardEntry> = rows
+                .into_iter()
+                .map(|row| {
+                    let mut entry = DashboardEntry::from(row);
+                    entry.regions = by_target.remove(&entry.id).unwrap_or_default();
+                    entry
+                })
+                .collect();
+            match query.group_by.as_deref() {
+                None => (StatusCode::OK, Json(entries)).into_response(),
+                Some("tag") => (StatusCode::OK, Json(group_by_tag(entries))).into_response(),
+                Some(other) => (StatusCode::BAD_REQUEST, format!("unsupported group_by '{other}': expected tag")).into_response(),
+            }
+        }
+        Err(e) => {
+            error!(error = %e, "failed to fetch dashboard");
+            db_error(&e)
+        }
+    }
+}
+
+/// Groups dashboard entries by tag; a target with several tags appears in each of its groups and
+/// untagged targets are listed under `untagged`.
+fn group_by_tag(entries: Vec<DashboardEntry>) -> BTreeMap<String, Vec<DashboardEntry>> {
+    let mut groups: BTreeMap<String, Vec<DashboardEntry>> = BTreeMap::new();
+    for entry in entries {
+        if entry.tags.is_empty() {
+            groups.entry(UNTAGGED_GROUP.to_string()).or_default().push(entry);
+            continue;
+        }
+        for tag in &entry.tags {
+            groups.entry(tag.clone()).or_default().push(entry.clone());
+        }
+    }
+    groups
+}
+
+#[instrument(skip(state))]
+async fn get_latency(
+    Path(target_id): Path<i32>,
+    Query(query): Query<WindowQuery>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let window_secs = match parse_window(query.window.as_deref().unwrap_or("1h")) {
+        Ok(secs) => secs,
+        Err(msg) => return (StatusCode::BAD_REQUEST, msg).into_response(),
+    };
+
+    let report = sqlx::query_as::<_, LatencyReport>(
+        r#"
+        SELECT
+            $1 AS target_id,
+            $2 AS window_secs,
+            COUNT(*) AS samples,
+            percentile_cont(0.50) WITHIN GROUP (ORDER BY response_time_ms) AS p50,
+            percentile_cont(0.90) WITHIN GROUP (ORDER BY response_time_ms) AS p90,
+            percentile_cont(0.95) WITHIN GROUP (ORDER BY response_time_ms) AS p95,
+            percentile_cont(0.99) WITHIN GROUP (ORDER BY response_time_ms) AS p99
+        FROM health_checks
+        WHERE target_id = $1
+          AND checked_at >= NOW() - ($2 * INTERVAL '1 second')
+          AND response_time_ms IS NOT NULL
+        "#
+    )
+    .bind(target_id)
+    .bind(window_secs)
+    .fetch_one(&state.pool)
+    .await;
+
+    match report {
+        Ok(report) => (StatusCode::OK, Json(report)).into_response(),
+        Err(e) => {
+            error!(error = %e, "failed to compute latency percentiles");
+            db_error(&e)
+        }
+    }
+}
+
+/// Distribution of a target's status codes over `window` (default `7d`), e.g. for SLA reports.
+#[instrument(skip(state))]
+async fn get_status_codes(
+    Path(target_id): Path<i32>,
+    Query(query): Query<WindowQuery>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let window_secs = match parse_window(query.window.as_deref().unwrap_or("7d")) {
+        Ok(secs) => secs,
+        Err(msg) => return (StatusCode::BAD_REQUEST, msg).into_response(),
+    };
+
+    let rows = sqlx::query_as::<_, (Option<i32>, i64)>(
+        r#"
+        SELECT status_code, COUNT(*)
+        FROM health_checks
+        WHERE target_id = $1 AND checked_at >= NOW() - ($2 * INTERVAL '1 second')
+        GROUP BY status_code
+        "#,
+    )
+    .bind(target_id)
+    .bind(window_secs)
+    .fetch_all(&state.pool)
+    .await;
+
+    match rows {
+        Ok(rows) => {
+            let total = rows.iter().map(|(_, count)| count).sum();
+            let codes = rows
+                .into_iter()
+                .map(|(code, count)| (code.map_or_else(|| ERROR_CODE_BUCKET.to_string(), |code| code.to_string()), count))
+                .collect();
+            (StatusCode::OK, Json(StatusCodeReport { target_id, window_secs, total, codes })).into_response()
+        }
+        Err(e) => {
+            error!(error = %e, "failed to count status codes");
+            db_error(&e)
+        }
+    }
+}
+
+/// Individual checks whose latency stands out from the rest of the window by more than `k`
+/// standard deviations.
+#[instrument(skip(state))]
+async fn get_anomalies(
+    Path(target_id): Path<i32>,
+    Query(query): Query<AnomalyQuery>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let window_secs = match parse_window(query.window.as_deref().unwrap_or("24h")) {
+        Ok(secs) => secs,
+        Err(msg) => return (StatusCode::BAD_REQUEST, msg).into_response(),
+    };
+    let k = query.k.unwrap_or(DEFAULT_ANOMALY_K);
+    if !(k.is_finite() && k > 0.0) {
+        return (StatusCode::BAD_REQUEST, "k must be a number greater than zero").into_response();
+    }
+
+    let stats = sqlx::query_as::<_, (i64, Option<f64>, Option<f64>)>(
+        r#"
+        SELECT COUNT(*), AVG(response_time_ms)::float8, STDDEV_POP(response_time_ms)::float8
+        FROM health_checks
+        WHERE target_id = $1
+          AND checked_at >= NOW() - ($2 * INTERVAL '1 second')
+          AND response_time_ms IS NOT NULL
+        "#,
+    )
+    .bind(target_id)
+    .bind(window_secs)
+    .fetch_one(&state.pool)
+    .await;
+    let (samples, mean_ms, stddev_ms) = match stats {
+        Ok(stats) => stats,
+        Err(e) => {
+            error!(error = %e, "failed to compute latency statistics");
+            return db_error(&e);
+        }
+    };
+
+    // With no spread nothing stands out, and the z-score would divide by zero
+    let anomalies = match (mean_ms, stddev_ms) {
+        (Some(mean), Some(stddev)) if stddev > 0.0 => {
+            sqlx::query_as::<_, Anomaly>(
+                r#"
+                SELECT id, checked_at, status_code, response_time_ms, (response_time_ms - $3) / $4 AS z_score
+                FROM health_checks
+                WHERE target_id = $1
+                  AND checked_at >= NOW() - ($2 * INTERVAL '1 second')
+                  AND response_time_ms > $3 + $5 * $4
+                ORDER BY checked_at DESC
+                "#,
+            )
+            .bind(target_id)
+            .bind(window_secs)
+            .bind(mean)
+            .bind(stddev)
+            .bind(k)
+            .fetch_all(&state.pool)
+            .await
+        }
+        _ => Ok(Vec::new()),
+    };
+
+    match anomalies {
+        Ok(anomalies) => {
+            let report = AnomalyReport { target_id, window_secs, k, samples, mean_ms, stddev_ms, anomalies };
+            (StatusCode::OK, Json(report)).into_response()
+        }
+        Err(e) => {
+            error!(error = %e, "failed to fetch latency anomalies");
+            db_error(&e)
+        }
+    }
+}
+
+/// Average latency and failure count per `bucket` over the window, aligned with `date_bin`.
+#[instrument(skip(state))]
+async fn get_timeseries(
+    Path(target_id): Path<i32>,
+    Query(query): Query<TimeseriesQuery>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let window_secs = match parse_window(query.window.as_deref().unwrap_or("24h")) {
+        Ok(secs) => secs,
+        Err(msg) => return (StatusCode::BAD_REQUEST, msg).into_response(),
+    };
+    let bucket_secs = match parse_window(query.bucket.as_deref().unwrap_or("5m"))
+        .and_then(|bucket| validate_buckets(window_secs, bucket).map(|()| bucket))
+    {
+        Ok(secs) => secs,
+        Err(msg) => return (StatusCode::BAD_REQUEST, msg).into_response(),
+    };
+
+    // The last bucket is the one containing now; the series walks back from it so the buckets
+    // cover the window.
+    let buckets = sqlx::query_as::<_, TimeseriesBucket>(
+        r#"
+        WITH bounds AS (
+            SELECT
+                $3 * INTERVAL '1 second' AS width,
+                date_bin($3 * INTERVAL '1 second', NOW(), TIMESTAMPTZ 'epoch') AS last_start
+        ),
+        series AS (
+            SELECT generate_series(last_start - ($2 / $3 - 1) * width, last_start, width) AS bucket_start
+            FROM bounds
+        ),
+        checks AS (
+            SELECT
+                date_bin($3 * INTERVAL '1 second', h.checked_at, TIMESTAMPTZ 'epoch') AS bucket_start,
+                h.response_time_ms,
+                NOT check_healthy(h, t) AS failed
+            FROM health_checks h
+            JOIN targets t ON t.id = h.target_id
+            WHERE h.target_id = $1
+              AND h.checked_at >= (SELECT MIN(bucket_start) FROM series)
+        )
+        SELECT
+            s.bucket_start,
+            COUNT(c.bucket_start) AS checks,
+            AVG(c.response_time_ms)::DOUBLE PRECISION AS avg_latency_ms,
+            CASE WHEN COUNT(c.bucket_start) > 0 THEN COUNT(*) FILTER (WHERE c.failed) END AS failures
+        FROM series s
+        LEFT JOIN checks c ON c.bucket_start = s.bucket_start
+        GROUP BY s.bucket_start
+        ORDER BY s.bucket_start
+        "#
+    )
+    .bind(target_id)
+    .bind(window_secs)
+    .bind(bucket_secs)
+    .fetch_all(&state.pool)
+    .await;
+
+    match buckets {
+        Ok(buckets) => {
+            let series = Timeseries { target_id, window_secs, bucket_secs, buckets };
+            (StatusCode::OK, Json(series)).into_response()
+        }
+        Err(e) => {
+            error!(error = %e, "failed to compute timeseries");
+            db_error(&e)
+        }
+    }
+}
+
+/// Requires the bucket to divide the window evenly into at most [`MAX_TIMESERIES_BUCKETS`] buckets.
+fn validate_buckets(window_secs: i64, bucket_secs: i64) -> Result<(), String> {
+    if bucket_secs > window_secs || window_secs % bucket_secs != 0 {
+        return Err(format!("bucket of {bucket_secs}s must divide the {window_secs}s window evenly"));
+    }
+    let count = window_secs / bucket_secs;
+    if count > MAX_TIMESERIES_BUCKETS {
+        return Err(format!("{count} buckets requested, at most {MAX_TIMESERIES_BUCKETS} allowed; use a larger bucket"));
+    }
+    Ok(())
+}
+
+/// Parses an optional RFC3339 query parameter, naming the parameter in the error message.
+fn parse_timestamp(name: &str, raw: Option<&str>) -> Result<Option<DateTime<Utc>>, String> {
+    raw.map(|raw| {
+        DateTime::parse_from_rfc3339(raw)
+            .map(|ts| ts.with_timezone(&Utc))
+            .map_err(|e| format!("invalid {name} timestamp '{raw}': {e} (expected RFC3339)"))
+    })
+    .transpose()
+}
+
+/// Parses a window like `30m`, `24h`, or `7d` into seconds.
+fn parse_window(raw: &str) -> Result<i64, String> {
+    let raw = raw.trim();
+    let invalid = || format!("invalid window '{raw}': expected a positive number followed by m, h, or d (e.g. 24h)");
+    let (num, multiplier) = if let Some(num) = raw.strip_suffix('m') {
+        (num, 60)
+    } else if let Some(num) = raw.strip_suffix('h') {
+        (num, 60 * 60)
+    } else if let Some(num) = raw.strip_suffix('d') {
+        (num, 24 * 60 * 60)
+    } else {
+        return Err(invalid());
+    };
+    let value: i64 = num.parse().map_err(|_| invalid())?;
+    if value <= 0 {
+        return Err(invalid());
+    }
+    value.checked_mul(multiplier).ok_or_else(invalid)
+}
+
+/// Checks that every custom header has a valid name and value.
+fn validate_headers(headers: &HashMap<String, String>) -> Result<(), String> {
+    for (name, value) in headers {
+        HeaderName::from_bytes(name.as_bytes()).map_err(|e| format!("invalid header name '{name}': {e}"))?;
+        HeaderValue::from_str(value).map_err(|e| format!("invalid value for header '{name}': {e}"))?;
+    }
+    Ok(())
+}
+
+/// Checks that a body assertion pattern compiles, reporting the compile error for a `400`.
+fn validate_body_regex(pattern: &str) -> Result<(), String> {
+    Regex::new(pattern)
+        .map(|_| ())
+        .map_err(|e| format!("invalid expected_body_regex: {e}"))
+}
+
+/// Normalizes an expected media type such as `application/json`, which is matched as a prefix.
+fn validate_content_type(raw: &str) -> Result<String, String> {
+    let content_type = raw.trim().to_ascii_lowercase();
+    let valid = content_type
+        .split_once('/')
+        .is_some_and(|(kind, _)| !kind.is_empty() && HeaderValue::from_str(&content_type).is_ok());
+    if !valid || content_type.contains(';') {
+        return Err(format!("invalid expected_content_type '{raw}': expected a media type such as application/json"));
+    }
+    Ok(content_type)
+}
+
+/// Lower-cases an expected `Content-Encoding`, which must be one the probe advertises.
+fn validate_content_encoding(raw: &str) -> Result<String, String> {
+    let encoding = raw.trim().to_ascii_lowercase();
+    if !ACCEPTED_ENCODINGS.split(", ").any(|accepted| accepted == encoding) {
+        return Err(format!("invalid expected_content_encoding '{raw}': expected one of {ACCEPTED_ENCODINGS}"));
+    }
+    Ok(encoding)
+}
+
+/// Checks header names and values, lower-casing the names so lookups and duplicates ignore case.
+fn validate_expected_headers(headers: &HashMap<String, String>) -> Result<HashMap<String, String>, String> {
+    let mut normalized = HashMap::new();
+    for (name, value) in headers {
+        let parsed = HeaderName::from_bytes(name.trim().as_bytes())
+            .map_err(|e| format!("invalid expected_headers name '{name}': {e}"))?;
+        let value = value.trim();
+        HeaderValue::from_str(value).map_err(|e| format!("invalid expected_headers value for '{name}': {e}"))?;
+        if normalized.insert(parsed.as_str().to_string(), value.to_string()).is_some() {
+            return Err(format!("duplicate expected_headers name '{name}'"));
+        }
+    }
+    Ok(normalized)
+}
+
+/// Checks a cron expression, adding a zero seconds field to standard five-field ones since the
+/// `cron` crate expects one. Returns the expression as stored.
+fn validate_cron_schedule(raw: &str) -> Result<String, String> {
+    let raw = raw.trim();
+    let expression = if raw.split_whitespace().count() == 5 { format!("0 {raw}") } else { raw.to_string() };
+    let schedule =
+        cron::Schedule::from_str(&expression).map_err(|e| format!("invalid cron_schedule '{raw}': {e}"))?;
+    if schedule.upcoming(Utc).next().is_none() {
+        return Err(format!("invalid cron_schedule '{raw}': it never fires again"));
+    }
+    Ok(expression)
+}
+
+/// A basic auth password is only sent along with a user name.
+fn validate_basic_auth(user: Option<&str>, pass: Option<&str>) -> Result<(), String> {
+    if user.is_some_and(|user| user.is_empty() || user.contains(':')) {
+        return Err("basic_auth_user must be non-empty and must not contain ':'".into());
+    }
+    if pass.is_some() && user.is_none() {
+        return Err("basic_auth_pass requires basic_auth_user".into());
+    }
+    Ok(())
+}
+
+/// Trims tags and drops duplicates, rejecting empty or overly long ones.
+fn validate_tags(tags: &[String]) -> Result<Vec<String>, String> {
+    let mut cleaned: Vec<String> = Vec::with_capacity(tags.len());
+    for tag in tags {
+        let tag = tag.trim();
+        if tag.is_empty() {
+            return Err("tags must not be empty".into());
+        }
+        if tag.len() > MAX_TAG_LEN {
+            return Err(format!("tag '{tag}' is longer than {MAX_TAG_LEN} characters"));
+        }
+        if !cleaned.iter().any(|t| t == tag) {
+            cleaned.push(tag.to_string());
+        }
+    }
+    Ok(cleaned)
+}
+
+/// Trims a status page name, rejecting empty or overly long ones.
+fn validate_display_name(raw: &str) -> Result<String, String> {
+    let name = raw.trim();
+    if name.is_empty() {
+        return Err("display_name must not be empty".into());
+    }
+    if name.chars().count() > MAX_DISPLAY_NAME_LEN {
+        return Err(format!("display_name is longer than {MAX_DISPLAY_NAME_LEN} characters"));
+    }
+    Ok(name.to_string())
+}
+
+/// Parses a status range spec such as `200-299,301`, returning it in the canonical form that is stored.
+fn validate_status_ranges(raw: &str) -> Result<String, String> {
+    raw.parse::<StatusRanges>().map(|ranges| ranges.to_string())
+}
+
+/// Methods accepted for HTTP checks.
+const HTTP_METHODS: [&str; 7] = ["GET", "HEAD", "POST", "PUT", "PATCH", "DELETE", "OPTIONS"];
+
+/// Normalizes an HTTP method to upper case, rejecting unknown methods and bodies on `GET`/`HEAD`.
+fn validate_http_method(raw: &str, request_body: Option<&str>) -> Result<String, String> {
+    let method = raw.trim().to_ascii_uppercase();
+    if !HTTP_METHODS.contains(&method.as_str()) {
+        return Err(format!("unsupported http_method '{raw}': expected one of {}", HTTP_METHODS.join(", ")));
+    }
+    if request_body.is_some() && matches!(method.as_str(), "GET" | "HEAD") {
+        return Err(format!("request_body is not allowed with http_method {method}"));
+    }
+    Ok(method)
+}
+
+/// Checks a target's address as its check type expects it, normalizing it as stored.
+fn validate_address(check_type: CheckType, raw: &str) -> Result<String, String> {
+    match check_type {
+        CheckType::Http | CheckType::Sequence => validate_url(raw),
+        CheckType::Tcp => validate_tcp_address(raw),
+        CheckType::Ping => validate_ping_host(raw),
+    }
+}
+
+/// Checks that `raw` is a `host:port` address, returning a message suitable for a `400` otherwise.
+fn validate_tcp_address(raw: &str) -> Result<String, String> {
+    let raw = raw.trim();
+    let invalid = |reason: &str| format!("invalid tcp address '{raw}': {reason}");
+    let (host, port) = raw.rsplit_once(':').ok_or_else(|| invalid("expected host:port"))?;
+    if host.is_empty() {
+        return Err(invalid("missing host"));
+    }
+    port.parse::<u16>().map_err(|_| invalid("port must be a number between 0 and 65535"))?;
+    Ok(raw.to_string())
+}
+
+/// Accepts a bare host name or IP address, as `ping` would.
+fn validate_ping_host(raw: &str) -> Result<String, String> {
+    let raw = raw.trim();
+    let invalid = |reason: &str| format!("invalid ping host '{raw}': {reason}");
+    if raw.is_empty() {
+        return Err(invalid("missing host"));
+    }
+    if raw.parse::<std::net::IpAddr>().is_ok() {
+        return Ok(raw.to_string());
+    }
+    let is_hostname = raw
+        .split('.')
+        .all(|label| !label.is_empty() && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'));
+    if !is_hostname {
+        return Err(invalid("expected a host name or IP address without scheme, port, or path"));
+    }
+    Ok(raw.to_string())
+}
+
+/// Sequence checks need steps and other check types must not have any.
+fn validate_steps(check_type: CheckType, steps: Option<&mut Vec<SequenceStep>>, url: &str) -> Result<(), String> {
+    match (check_type, steps) {
+        (CheckType::Sequence, Some(steps)) => sequence::validate_steps(steps, url),
+        (CheckType::Sequence, None) => Err("sequence checks require steps".into()),
+        (_, Some(_)) => Err("steps are only allowed for sequence checks".into()),
+        (_, None) => Ok(()),
+    }
+}
+
+// --------- Background worker ---------
+
+/// Longest the worker sleeps between ticks, so new and edited targets are picked up; it wakes
+/// sooner when a target is due before then.
+const WORKER_TICK: Duration = Duration::from_secs(5);
+
+/// Check interval applied to targets without an explicit `check_interval_secs`, unless overridden
+/// by `CHECK_INTERVAL_SECS`.
+const DEFAULT_CHECK_INTERVAL_SECS: u64 = 60;
+
+/// Region recorded with checks when `REGION` is unset.
+const DEFAULT_REGION: &str = "default";
+
+/// Request timeout applied to targets without an explicit `timeout_ms`.
+const DEFAULT_TIMEOUT_MS: u64 = 20_000;
+
+/// Status recorded for successful non-HTTP checks such as TCP connects and pings.
+const SYNTHETIC_OK_STATUS: i32 = 200;
+
+/// Redirect hops followed before a check is recorded as failed (e.g. a redirect loop).
+const MAX_REDIRECTS: i32 = 10;
+
+/// `Accept-Encoding` sent with HTTP checks; `read_body` decodes each of these.
+const ACCEPTED_ENCODINGS: &str = "gzip, br, deflate";
+
+/// Upper bound on how much of a response body is buffered for content assertions.
+const MAX_BUFFERED_BODY_BYTES: usize = 1024 * 1024;
+
+/// Response body cap when `MAX_BODY_BYTES` is unset.
+const DEFAULT_MAX_BODY_BYTES: u64 = 5 * 1024 * 1024;
+
+/// `User-Agent` sent with checks unless `CHECK_USER_AGENT` overrides it.
+const DEFAULT_USER_AGENT: &str = concat!("devops-health-monitor/", env!("CARGO_PKG_VERSION"));
+
+/// Longest tag accepted on a target.
+const MAX_TAG_LEN: usize = 64;
+
+/// Longest display name accepted on a target.
+const MAX_DISPLAY_NAME_LEN: usize = 100;
+
+/// Dashboard group holding targets without any tags when grouping by tag.
+const UNTAGGED_GROUP: &str = "untagged";
+
+/// Encoded CSV rows queued ahead of a slow export client.
+const EXPORT_CHANNEL_CAPACITY: usize = 64;
+
+/// Responses smaller than this are sent uncompressed, where compression saves little.
+const COMPRESSION_MIN_BYTES: u16 = 1024;
+
+/// Checks buffered per `/api/live` or `/api/events` subscriber before it is considered lagging and dropped.
+const LIVE_CHANNEL_CAPACITY: usize = 256;
+
+/// How often an idle `/api/events` stream sends a keep-alive comment, so proxies don't close it.
+const SSE_KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Certificates expiring within this many days are logged as warnings when refreshed.
+const CERT_EXPIRY_WARNING_DAYS: i64 = 14;
+
+/// How long a stored certificate expiry is trusted before the next check refreshes it.
+const CERT_REFRESH_INTERVAL_SECS: i64 = 24 * 60 * 60;
+
+/// Delay before the first retry of a failed request; doubled for each further attempt.
+const RETRY_BASE_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Periodically fetches targets and checks the HTTP status and latency of every target whose
+/// own check interval has elapsed since it was last checked, or whose cron schedule has fired.
+/// Between ticks it sleeps until the soonest target is due, at most one [`WORKER_TICK`].
+///
+/// The first tick waits for `STARTUP_DELAY_SECS`, plus one [`WORKER_TICK`] unless
+/// `RUN_FIRST_TICK_IMMEDIATELY` is set. Stops once `shutdown` is cancelled, including during that
+/// wait; an in-progress tick is allowed to finish first.
+fn start_background_worker(state: AppState, shutdown: CancellationToken) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        // When each target was last checked; interval targets missing here are due immediately.
+        let mut last_checked: HashMap<i32, Instant> = HashMap::new();
+        let mut rng = StdRng::from_entropy();
+
+        let mut wait = state.worker.startup_delay;
+        if !state.worker.run_first_tick_immediately {
+            wait += WORKER_TICK;
+        }
+        if !wait.is_zero() {
+            info!(wait_secs = wait.as_secs(), "delaying first worker tick");
+        }
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = sleep(wait) => {}
+            }
+            let next_due = match tick(&state, &mut last_checked, &mut rng).await {
+                Ok(next_due) => next_due,
+                Err(e) => {
+                    error!(error = %e, "background tick failed");
+                    None
+                }
+            };
+            wait = next_due.map_or(WORKER_TICK, |at| at.saturating_duration_since(Instant::now()).min(WORKER_TICK));
+        }
+        info!("background worker stopped");
+    })
+}
+
+/// Checks every due target, returning when the soonest target will next be due, if any are enabled.
+/// In maintenance mode nothing is checked or recorded, though the tick still counts as a heartbeat.
+#[instrument(skip(state, last_checked, rng))]
+pub async fn tick(
+    state: &AppState,
+    last_checked: &mut HashMap<i32, Instant>,
+    rng: &mut StdRng,
+) -> anyhow::Result<Option<Instant>> {
+    let started = Instant::now();
+    let config = &state.worker;
+    if service_in_maintenance(&state.pool).await? {
+        debug!("service in maintenance mode, skipping checks");
+        state.record_tick(started, 0);
+        return Ok(None);
+    }
+    record_db_latency(state).await;
+    let targets = sqlx::query_as::<_, Target>(&format!("SELECT {TARGET_COLUMNS} FROM targets WHERE enabled"))
+        .fetch_all(&state.pool)
+        .await?;
+
+    // Forget targets that have been deleted or paused since the previous tick, so a resumed
+    // target is checked straight away
+    last_checked.retain(|id, _| targets.iter().any(|t| t.id == *id));
+    state.body_regexes.retain(|id| targets.iter().any(|t| t.id == id));
+    state.host_limits.retain(|host| targets.iter().any(|t| t.host() == host));
+    state.burn_refresh.retain(|id| targets.iter().any(|t| t.id == id));
+
+    // A cron target isn't due when first seen, only once its schedule next fires
+    let now = Instant::now();
+    for t in targets.iter().filter(|t| t.cron_schedule.is_some()) {
+        last_checked.entry(t.id).or_insert(now);
+    }
+    let (due, waiting): (Vec<Target>, Vec<Target>) = targets
+        .into_iter()
+        .partition(|t| last_checked.get(&t.id).is_none_or(|&at| next_due(t, at, config.check_interval) <= now));
+
+    for t in &due {
+        last_checked.insert(t.id, now);
+    }
+    let wake_at = due
+        .iter()
+        .chain(&waiting)
+        .map(|t| next_due(t, last_checked[&t.id], config.check_interval))
+        .min();
+
+    let targets_checked = due.len();
+
+    // Spread checks over the jitter window so targets sharing a downstream aren't hit at once
+    let jitter = (config.jitter_ms > 0).then(|| Uniform::new_inclusive(0, config.jitter_ms));
+    let scheduled: Vec<(Target, Duration)> = due
+        .into_iter()
+        .map(|t| {
+            let delay = jitter.map_or(Duration::ZERO, |dist| Duration::from_millis(dist.sample(rng)));
+            (t, delay)
+        })
+        .collect();
+
+    // Each check records its own result, so a failing insert never affects the others
+    let slots = &Semaphore::new(config.concurrency);
+    stream::iter(scheduled)
+        .for_each_concurrent(None, |(t, delay)| async move {
+            if !delay.is_zero() {
+                sleep(delay).await;
+            }
+            if let Err(e) = run_limited_check(state, &t, slots).await {
+                error!(target_id = t.id, error = %e, "failed to record health check");
+            }
+        })
+        .await;
+
+    state.record_tick(started, targets_checked);
+    Ok(wake_at)
+}
+
+/// Times a `SELECT 1` on the worker's pool and stores it in `db_latency_samples`, so a degrading
+/// database shows up even while every target looks healthy. Failures are logged and skipped.
+async fn record_db_latency(state: &AppState) {
+    let start = Instant::now();
+    if let Err(e) = sqlx::query("SELECT 1").execute(&state.pool).await {
+        error!(error = %e, "database latency probe failed");
+        return;
+    }
+    let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
+    metrics::gauge!("worker_db_latency_ms").set(latency_ms);
+
+    if let Err(e) = sqlx::query("INSERT INTO db_latency_samples (latency_ms, region) VALUES ($1, $2)")
+        .bind(latency_ms)
+        .bind(&state.worker.region)
+        .execute(&state.pool)
+        .await
+    {
+        error!(error = %e, "failed to store database latency sample");
+    }
+}
+
+/// Probes one target and stores the outcome as a `health_checks` row, returning the stored row.
+///
+/// A target that could not be reached (after `retries`), or whose body exceeded `max_body_bytes`,
+/// is still recorded, with a null status. `body_regex` is the target's compiled
+/// `expected_body_regex`, if it has one, and `region` is stored with the row. Older checks beyond
+/// the target's `max_history` are deleted afterwards.
+///
+/// Each invocation gets a fresh request id, logged as `request_id` and sent to HTTP targets as
+/// `X-Request-Id` on every attempt.
+#[instrument(skip_all, fields(request_id = field::Empty))]
+pub async fn check_one(
+    pool: &PgPool,
+    client: &reqwest::Client,
+    target: &Target,
+    body_regex: Option<&Regex>,
+    retries: u32,
+    region: &str,
+    max_body_bytes: u64,
+) -> anyhow::Result<HealthCheckRecord> {
+    let request_id = Uuid::new_v4();
+    Span::current().record("request_id", field::display(request_id));
+    let outcome = match probe_with_retries(client, target, body_regex, retries, request_id, max_body_bytes).await {
+        Ok(outcome) => outcome,
+        Err(err) => CheckOutcome { error_kind: Some(err.kind()), failed_step: err.failed_step(), ..Default::default() },
+    };
+
+    let record = sqlx::query_as::<_, HealthCheckRecord>(
+        r#"
+        INSERT INTO health_checks (
+            target_id, status_code, response_time_ms, redirect_count, body_match, dns_ms, content_type_match, region,
+            http_version, error_kind, ttfb_ms, content_length_bytes, failed_step, headers_match, resolved_ip,
+            content_encoding, encoding_match
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17)
+        RETURNING id, target_id, checked_at, status_code, response_time_ms, ttfb_ms, content_length_bytes, redirect_count,
+            body_match, dns_ms, content_type_match, headers_match, content_encoding, encoding_match, region, http_version,
+            error_kind, failed_step, resolved_ip
+        "#,
+    )
+    .bind(target.id)
+    .bind(outcome.status)
+    .bind(outcome.latency_ms)
+    .bind(outcome.redirect_count)
+    .bind(outcome.body_match)
+    .bind(outcome.dns_ms)
+    .bind(outcome.content_type_match)
+    .bind(region)
+    .bind(outcome.http_version)
+    .bind(outcome.error_kind)
+    .bind(outcome.ttfb_ms)
+    .bind(outcome.content_length_bytes)
+    .bind(outcome.failed_step)
+    .bind(outcome.headers_match)
+    .bind(outcome.resolved_ip)
+    .bind(outcome.content_encoding)
+    .bind(outcome.encoding_match)
+    .fetch_one(pool)
+    .await?;
+
+    if let Some(max_history) = target.max_history {
+        trim_history(pool, target.id, max_history).await?;
+    }
+
+    Ok(record)
+}
+
+/// Deletes a target's checks beyond the newest `max_history`. Both the inner scan and the delete
+/// walk `idx_health_checks_target_checked_at`, so this stays cheap however long the history is.
+async fn trim_history(pool: &PgPool, target_id: i32, max_history: i32) -> sqlx::Result<u64> {
+    let deleted = sqlx::query(
+        r#"
+        DELETE FROM health_checks
+        WHERE target_id = $1
+          AND id NOT IN (
+              SELECT id FROM health_checks
+              WHERE target_id = $1
+              ORDER BY checked_at DESC
+              LIMIT $2
+          )
+        "#,
+    )
+    .bind(target_id)
+    .bind(i64::from(max_history))
+    .execute(pool)
+    .await?
+    .rows_affected();
+    Ok(deleted)
+}
+
+/// Runs [`run_check`] once both a permit for the target's host and one of `slots` are free. The
+/// host permit is taken first, so checks queued behind a slow host don't hold slots that checks
+/// of other hosts could use.
+async fn run_limited_check(state: &AppState, t: &Target, slots: &Semaphore) -> anyhow::Result<HealthCheckRecord> {
+    let host = state.host_limits.get(&t.host(), state.worker.max_checks_per_host);
+    let _host_permit = host.acquire().await?;
+    let _slot = slots.acquire().await?;
+    run_check(state, t).await
+}
+
+/// Runs [`check_one`] and everything that follows a recorded check: metrics, the live feed,
+/// certificate tracking, the failure streak, and alerts. Shared by the worker and on-demand checks.
+#[instrument(skip_all, fields(target_id = t.id, host = %t.log_host()))]
+async fn run_check(state: &AppState, t: &Target) -> anyhow::Result<HealthCheckRecord> {
+    let body_regex = state.body_regexes.get(t);
+    let insecure = t.insecure_skip_tls_verify && matches!(t.check_type, CheckType::Http | CheckType::Sequence);
+    if insecure {
+        warn!(target = %t.log_host(), "TLS certificate verification disabled for this check");
+    }
+    let client = state.clients.get(insecure, t.ip_family);
+    let stored = check_one(
+        &state.pool,
+        client,
+        t,
+        body_regex.as_ref(),
+        state.worker.retries,
+        &state.worker.region,
+        state.worker.max_body_bytes,
+    )
+    .await;
+    state.stats.checks.fetch_add(1, Ordering::Relaxed);
+    let record = stored.inspect_err(|_| {
+        state.stats.insert_errors.fetch_add(1, Ordering::Relaxed);
+    })?;
+    let (status, latency_ms) = (record.status_code, record.response_time_ms);
+    let healthy = is_check_healthy(
+        status,
+        t.expected_status,
+        t.expected_status_ranges.as_ref(),
+        &[record.body_match, record.content_type_match, record.headers_match, record.encoding_match],
+    );
+    if !healthy {
+        state.stats.failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    publish_live(state, t, StatusRecord { record: record.clone(), healthy });
+    record_check_metrics(t, status, latency_ms, healthy);
+
+    if t.check_type == CheckType::Http && t.url.starts_with("https://") {
+        record_cert_expiry(state, t).await;
+    }
+
+    // Only healthy checks decide whether a target is slow; failed ones leave the flag alone so
+    // a recovery doesn't repeat a slow alert that is still in effect
+    let slow = healthy.then(|| is_slow(latency_ms, t.latency_threshold_ms));
+
+    // Track the failure streak and slow flag on the target row, returning them from before and
+    // after this check, and fold the latency into its moving average (seeded by the first sample)
+    let streak = sqlx::query_as::<_, (i32, i32, bool, bool)>(
+        r#"
+        UPDATE targets t
+        SET consecutive_failures = CASE WHEN $2 THEN 0 ELSE t.consecutive_failures + 1 END,
+            latency_ema_ms = CASE
+                WHEN $3::integer IS NULL THEN t.latency_ema_ms
+                WHEN t.latency_ema_ms IS NULL THEN $3
+                ELSE $4 * $3 + (1 - $4) * t.latency_ema_ms
+            END,
+            slow = COALESCE($5, t.slow)
+        FROM (SELECT consecutive_failures, slow FROM targets WHERE id = $1) previous
+        WHERE t.id = $1
+        RETURNING previous.consecutive_failures, t.consecutive_failures, previous.slow, t.slow
+        "#,
+    )
+    .bind(t.id)
+    .bind(healthy)
+    .bind(latency_ms)
+    .bind(state.worker.ema_alpha)
+    .bind(slow)
+    .fetch_one(&state.pool)
+    .await;
+
+    // Alert once the streak reaches the threshold, and on recovery only if that alert was due
+    let threshold = state.worker.alert_after_failures;
+    match streak {
+        Ok((previous, current, was_slow, now_slow)) => {
+            let kind = if healthy && previous >= threshold {
+                Some(AlertKind::Up)
+            } else if !healthy && current == threshold {
+                Some(AlertKind::Down)
+            } else if !healthy && current > threshold && state.alerter.reminder_due(t.id) {
+                Some(AlertKind::StillDown)
+            } else {
+                None
+            };
+            if let Some(kind) = kind {
+                alert_transition(state, t, Alert { kind, url: &t.url, status, latency_ms, metadata: &t.metadata }).await;
+            }
+            if now_slow && !was_slow {
+                let alert = Alert { kind: AlertKind::Slow, url: &t.url, status, latency_ms, metadata: &t.metadata };
+                alert_transition(state, t, alert).await;
+            } else if was_slow && !now_slow {
+                info!(target = %t.log_host(), latency_ms, "target latency back under threshold");
+            }
+        }
+        Err(e) => error!(target_id = t.id, error = %e, "failed to update consecutive failures"),
+    }
+
+    track_burn_rate(state, t, status, latency_ms).await;
+
+    Ok(record)
+}
+
+/// Recomputes the target's burn rate after a check, at most once per [`BURN_RATE_REFRESH`],
+/// alerting once when both windows cross their thresholds; the `burning` flag on the target row
+/// keeps it from repeating until it clears.
+async fn track_burn_rate(state: &AppState, t: &Target, status: Option<i32>, latency_ms: Option<i32>) {
+    if !state.burn_refresh.due(t.id) {
+        return;
+    }
+    let report = match burn_rate_report(&state.pool, state.burn, t.id).await {
+        Ok(report) => report,
+        Err(e) => {
+            error!(target_id = t.id, error = %e, "failed to compute burn rate");
+            return;
+        }
+    };
+    let was_burning = sqlx::query_scalar::<_, bool>(
+        r#"
+        UPDATE targets t SET burning = $2
+        FROM (SELECT burning FROM targets WHERE id = $1) previous
+        WHERE t.id = $1
+        RETURNING previous.burning
+        "#,
+    )
+    .bind(t.id)
+    .bind(report.burning)
+    .fetch_optional(&state.pool)
+    .await;
+
+    match was_burning {
+        Ok(Some(false)) if report.burning => {
+            warn!(
+                target = %t.log_host(),
+                short_burn_rate = report.short_burn_rate,
+                long_burn_rate = report.long_burn_rate,
+                "target is burning its error budget"
+            );
+            let alert = Alert { kind: AlertKind::BurnRate, url: &t.url, status, latency_ms, metadata: &t.metadata };
+            alert_transition(state, t, alert).await;
+        }
+        Ok(Some(true)) if !report.burning => {
+            info!(target = %t.log_host(), "target burn rate back under threshold");
+        }
+        Ok(_) => {}
+        Err(e) => error!(target_id = t.id, error = %e, "failed to update burn rate flag"),
+    }
+}
+
+/// Whether a check's latency exceeds the target's `latency_threshold_ms`, if it has one.
+fn is_slow(latency_ms: Option<i32>, threshold_ms: Option<i32>) -> bool {
+    matches!((latency_ms, threshold_ms), (Some(ms), Some(max)) if ms > max)
+}
+
+/// Sends the alert for a status transition unless the service is in maintenance mode, the
+/// target is in a maintenance window, flapping, or within its alert cooldown (recoveries bypass
+/// the cooldown). If any lookup fails the alert is sent anyway.
+async fn alert_transition(state: &AppState, t: &Target, alert: Alert<'_>) {
+    match service_in_maintenance(&state.pool).await {
+        Ok(true) => {
+            info!(target = %t.log_host(), kind = ?alert.kind, "service in maintenance mode, alert suppressed");
+            return;
+        }
+        Ok(false) => {}
+        Err(e) => error!(error = %e, "failed to look up maintenance mode"),
+    }
+
+    match in_maintenance(&state.pool, t.id).await {
+        Ok(true) => {
+            info!(target = %t.log_host(), kind = ?alert.kind, "target in maintenance, alert suppressed");
+            return;
+        }
+        Ok(false) => {}
+        Err(e) => error!(target_id = t.id, error = %e, "failed to look up maintenance windows"),
+    }
+
+    // Suppress alerts while a target is flapping to avoid an alert storm
+    match flap_report(&state.pool, state.flap, t.id).await {
+        Ok(report) if report.is_flapping => {
+            info!(target = %t.log_host(), transitions = report.transitions, kind = ?alert.kind, "target is flapping, alert suppressed");
+            return;
+        }
+        Ok(_) => {}
+        Err(e) => error!(target_id = t.id, error = %e, "failed to compute flap report"),
+    }
+
+    if alert.kind != AlertKind::Up && state.alerter.in_cooldown(t.id) {
+        info!(target = %t.log_host(), kind = ?alert.kind, "target alerted recently, alert suppressed");
+        return;
+    }
+
+    // Without a matching route (or when routes can't be loaded) the default sinks are alerted
+    let routes = alert_routes_for(&state.pool, t.id).await.unwrap_or_else(|e| {
+        error!(target_id = t.id, error = %e, "failed to look up alert routes");
+        Vec::new()
+    });
+
+    state.alerter.start_cooldown(t.id);
+    let Deliveries { sent, email } = state.alerter.notify(&alert, &routes).await;
+    for delivery in sent {
+        record_alert(&state.pool, t.id, alert.kind, delivery.channel, delivery.ok).await;
+    }
+    if let Some(email) = email {
+        let (pool, target_id, kind) = (state.pool.clone(), t.id, alert.kind);
+        tokio::spawn(async move {
+            let ok = email.await.unwrap_or(false);
+            record_alert(&pool, target_id, kind, "email", ok).await;
+        });
+    }
+}
+
+/// Destinations of every alert route matching the target by tag or `metadata.severity`, each
+/// destination once. Rows that no longer validate are skipped with a warning.
+async fn alert_routes_for(pool: &PgPool, target_id: i32) -> sqlx::Result<Vec<Destination>> {
+    let rows = sqlx::query_as::<_, (i32, Option<String>, Option<String>, Option<String>)>(
+        r#"
+        SELECT DISTINCT ON (r.webhook_url, r.email) r.id, r.webhook_url, r.template, r.email
+        FROM alert_routes r
+        JOIN targets t ON t.id = $1
+        WHERE (r.tag IS NULL OR r.tag = ANY(t.tags))
+          AND (r.severity IS NULL OR r.severity = LOWER(t.metadata->>'severity'))
+        ORDER BY r.webhook_url, r.email, r.id
+        "#,
+    )
+    .bind(target_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .filter_map(|(route_id, webhook_url, template, email)| {
+            Destination::new(webhook_url.as_deref(), template.as_deref(), email.as_deref())
+                .inspect_err(|e| warn!(route_id, error = %e, "skipping invalid alert route"))
+                .ok()
+        })
+        .collect())
+}
+
+/// Adds a delivery to the `alerts` audit trail, logging rather than returning failures.
+async fn record_alert(pool: &PgPool, target_id: i32, kind: AlertKind, channel: &str, delivery_ok: bool) {
+    if let Err(e) = sqlx::query("INSERT INTO alerts (target_id, kind, channel, delivery_ok) VALUES ($1, $2, $3, $4)")
+        .bind(target_id)
+        .bind(kind.as_str())
+        .bind(channel)
+        .bind(delivery_ok)
+        .execute(pool)
+        .await
+    {
+        error!(target_id, channel, error = %e, "failed to record alert");
+    }
+}
+
+/// Whether the current time falls inside one of the target's maintenance windows.
+async fn in_maintenance(pool: &PgPool, target_id: i32) -> sqlx::Result<bool> {
+    sqlx::query_scalar::<_, bool>(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM maintenance_windows
+            WHERE target_id = $1 AND starts_at <= NOW() AND ends_at > NOW()
+        )
+        "#,
+    )
+    .bind(target_id)
+    .fetch_one(pool)
+    .await
+}
+
+/// Broadcasts a recorded check to `/api/live` and `/api/events` subscribers, if there are any.
+fn publish_live(state: &AppState, t: &Target, check: StatusRecord) {
+    if state.live.receiver_count() == 0 {
+        return;
+    }
+    match serde_json::to_string(&LiveCheck { url: &t.url, check }) {
+        // Sending only fails when every subscriber disconnected in the meantime
+        Ok(message) => drop(state.live.send(LiveUpdate { target_id: t.id, message })),
+        Err(e) => error!(target_id = t.id, error = %e, "failed to serialize live check"),
+    }
+}
+
+/// Measurements from one check, as stored in `health_checks`; all `None` when the check failed.
+#[derive(Debug, Default)]
+struct CheckOutcome {
+    status: Option<i32>,
+    latency_ms: Option<i32>,
+    /// Time until the final response's headers arrived (HTTP targets only); `latency_ms` also
+    /// covers draining the body.
+    ttfb_ms: Option<i32>,
+    /// Bytes of response body drained (HTTP targets only).
+    content_length_bytes: Option<i64>,
+    /// Redirects followed to reach the final response (HTTP targets that follow redirects only).
+    redirect_count: Option<i32>,
+    /// Whether the body contained `expected_body_substring` and matched `expected_body_regex`
+    /// (only when either is configured).
+    body_match: Option<bool>,
+    /// Host name resolution time, measured separately before the request.
+    dns_ms: Option<i32>,
+    /// Whether the `Content-Type` header matched `expected_content_type` (only when one is configured).
+    content_type_match: Option<bool>,
+    /// Whether the response headers satisfied `expected_headers` (only when some are configured).
+    headers_match: Option<bool>,
+    /// The final response's `Content-Encoding` as received, lower-cased.
+    content_encoding: Option<String>,
+    /// Whether it matched `expected_content_encoding` (only when one is configured).
+    encoding_match: Option<bool>,
+    /// Protocol of the final response, e.g. `HTTP/1.1` or `HTTP/2`.
+    http_version: Option<String>,
+    /// Category of the final failure, set only when no attempt got a response.
+    error_kind: Option<&'static str>,
+    /// Index of the step that failed a sequence check.
+    failed_step: Option<i32>,
+    /// Address the (final) connection was made to.
+    resolved_ip: Option<String>,
+    /// URL and `Content-Type` of the final response (HTTP targets only); reported by dry runs but
+    /// not stored.
+    final_url: Option<String>,
+    content_type: Option<String>,
+}
+
+/// Refreshes the stored TLS certificate expiry for an HTTPS target, warning when it is close.
+/// Certificates change rarely, so this only takes the extra handshake when the stored expiry is
+/// older than [`CERT_REFRESH_INTERVAL_SECS`]; claiming the refresh first keeps concurrent checks
+/// (e.g. from several regions) from all doing it, and a failed handshake releases the claim so
+/// the next check tries again.
+async fn record_cert_expiry(state: &AppState, t: &Target) {
+    let claimed = sqlx::query_scalar::<_, i32>(
+        r#"
+        UPDATE targets SET cert_checked_at = NOW()
+        WHERE id = $1 AND (cert_checked_at IS NULL OR cert_checked_at < NOW() - ($2 * INTERVAL '1 second'))
+        RETURNING id
+        "#,
+    )
+    .bind(t.id)
+    .bind(CERT_REFRESH_INTERVAL_SECS)
+    .fetch_optional(&state.pool)
+    .await;
+    match claimed {
+        Ok(Some(_)) => {}
+        Ok(None) => return,
+        Err(e) => {
+            error!(target_id = t.id, error = %e, "failed to claim certificate refresh");
+            return;
+        }
+    }
+
+    let expires_at = match tls::fetch_cert_expiry(&t.url, request_timeout(t)).await {
+        Ok(expires_at) => expires_at,
+        Err(e) => {
+            warn!(target = %t.log_host(), error = %e, "failed to inspect TLS certificate");
+            if let Err(e) = sqlx::query(r#"UPDATE targets SET cert_checked_at = NULL WHERE id = $1"#)
+                .bind(t.id)
+                .execute(&state.pool)
+                .await
+            {
+                error!(target_id = t.id, error = %e, "failed to release certificate refresh");
+            }
+            return;
+        }
+    };
+
+    let days_left = (expires_at - Utc::now()).num_days();
+    if days_left < CERT_EXPIRY_WARNING_DAYS {
+        warn!(target = %t.log_host(), %expires_at, days_left, "TLS certificate expires soon");
+    }
+
+    if let Err(e) = sqlx::query(r#"UPDATE targets SET cert_expires_at = $2 WHERE id = $1"#)
+        .bind(t.id)
+        .bind(expires_at)
+        .execute(&state.pool)
+        .await
+    {
+        error!(target_id = t.id, error = %e, "failed to store certificate expiry");
+    }
+}
+
+/// Requests a target, retrying failed attempts with exponential backoff.
+///
+/// Only the final failure is reported; a retry that succeeds returns that attempt's latency.
+async fn probe_with_retries(
+    client: &reqwest::Client,
+    t: &Target,
+    body_regex: Option<&Regex>,
+    retries: u32,
+    request_id: Uuid,
+    max_body_bytes: u64,
+) -> Result<CheckOutcome, ProbeError> {
+    let timeout = request_timeout(t);
+    let mut attempt = 0;
+    loop {
+        match probe(client, t, body_regex, timeout, request_id, max_body_bytes).await {
+            Ok(outcome) => return Ok(outcome),
+            Err(err) if attempt < retries && err.is_retryable() => {
+                let backoff = RETRY_BASE_BACKOFF.saturating_mul(2u32.saturating_pow(attempt));
+                attempt += 1;
+                warn!(target = %t.log_host(), error = %err, attempt, backoff_ms = backoff.as_millis() as u64, "request failed, retrying");
+                sleep(backoff).await;
+            }
+            Err(err) if err.is_timeout() => {
+                error!(target = %t.log_host(), timeout_ms = timeout.as_millis() as u64, "request timed out");
+                return Err(err);
+            }
+            Err(err) => {
+                error!(target = %t.log_host(), error = %err, kind = err.kind(), "request failed");
+                return Err(err);
+            }
+        }
+    }
+}
+
+/// Probes a target once.
+async fn probe(
+    client: &reqwest::Client,
+    t: &Target,
+    body_regex: Option<&Regex>,
+    timeout: Duration,
+    request_id: Uuid,
+    max_body_bytes: u64,
+) -> Result<CheckOutcome, ProbeError> {
+    match t.check_type {
+        CheckType::Http => probe_http(client, t, body_regex, timeout, request_id, max_body_bytes).await,
+        CheckType::Tcp => probe_tcp(t, timeout).await,
+        CheckType::Ping => probe_ping(t, timeout).await,
+        CheckType::Sequence => sequence::probe(client, t, timeout, request_id, max_body_bytes).await,
+    }
+}
+
+/// Issues an HTTP request, following redirects itself when the target allows it so hops can be
+/// counted, and returns the final status with the full response time.
+///
+/// The client never follows redirects on its own; with `follow_redirects` off the 3xx is recorded as-is.
+async fn probe_http(
+    client: &reqwest::Client,
+    t: &Target,
+    body_regex: Option<&Regex>,
+    timeout: Duration,
+    request_id: Uuid,
+    max_body_bytes: u64,
+) -> Result<CheckOutcome, ProbeError> {
+    let origin = reqwest::Url::parse(&t.url).ok();
+    let dns_ms = match &origin {
+        Some(origin) => resolve_time(origin, timeout).await,
+        None => None,
+    };
+    let start = Instant::now();
+    let mut url = t.url.clone();
+    let mut method = reqwest::Method::from_bytes(t.http_method.as_bytes()).unwrap_or(reqwest::Method::GET);
+    let mut body = t.request_body.clone();
+    let mut redirects = 0;
+
+    let resp = loop {
+        let mut request = client
+            .request(method.clone(), &url)
+            .timeout(timeout)
+            .header(REQUEST_ID_HEADER, request_id.to_string())
+            .header(header::ACCEPT_ENCODING, ACCEPTED_ENCODINGS);
+        if let Some(body) = &body {
+            request = request.body(body.clone());
+        }
+        // Like reqwest's own redirect handling, don't leak custom (often auth) headers to other hosts
+        let same_origin = reqwest::Url::parse(&url).ok().map(|u| u.origin()) == origin.as_ref().map(|u| u.origin());
+        if same_origin {
+            request = request.headers(custom_headers(t));
+            if let Some(user) = &t.basic_auth_user {
+                request = request.basic_auth(user, t.basic_auth_pass.as_ref());
+            }
+        }
+        let resp = request.send().await?;
+
+        let next = (t.follow_redirects && resp.status().is_redirection())
+            .then(|| redirect_location(&resp))
+            .flatten();
+        match next {
+            Some(_) if redirects >= MAX_REDIRECTS => return Err(ProbeError::TooManyRedirects),
+            Some(next) => {
+                redirects += 1;
+                url = next;
+                // As browsers do, 301/302/303 continue as a bodiless GET; 307/308 replay the request
+                let code = resp.status().as_u16();
+                if matches!(code, 301..=303) && method != reqwest::Method::HEAD {
+                    method = reqwest::Method::GET;
+                    body = None;
+                }
+            }
+            None => break resp,
+        }
+    };
+
+    let ttfb_ms = start.elapsed().as_millis() as i32;
+    let status = resp.status().as_u16() as i32;
+    let http_version = http_version_label(resp.version());
+    let content_type_match = t
+        .expected_content_type
+        .as_deref()
+        .map(|expected| content_type_matches(expected, resp.headers().get(header::CONTENT_TYPE)));
+    let headers_match = t.expected_headers.as_ref().map(|expected| expected_headers_match(&expected.0, resp.headers()));
+    let content_encoding = content_encoding(resp.headers());
+    let encoding_match = t.expected_content_encoding.as_deref().map(|expected| content_encoding.as_deref() == Some(expected));
+    let final_url = resp.url().to_string();
+    let resolved_ip = resp.remote_addr().map(|addr| addr.ip().to_string());
+    let content_type = resp.headers().get(header::CONTENT_TYPE).and_then(|value| value.to_str().ok()).map(str::to_string);
+    let (body, content_length) = read_body(resp, t, max_body_bytes).await?; // drain and decode body to measure full latency
+    let body_match = body_matches(t, body_regex, &String::from_utf8_lossy(&body));
+    Ok(CheckOutcome {
+        status: Some(status),
+        latency_ms: Some(start.elapsed().as_millis() as i32),
+        ttfb_ms: Some(ttfb_ms),
+        content_length_bytes: Some(content_length as i64),
+        redirect_count: t.follow_redirects.then_some(redirects),
+        body_match,
+        dns_ms,
+        content_type_match,
+        headers_match,
+        content_encoding,
+        encoding_match,
+        http_version: Some(http_version.to_string()),
+        resolved_ip,
+        final_url: Some(final_url),
+        content_type,
+        ..Default::default()
+    })
+}
+
+/// How a protocol version is recorded in `health_checks.http_version`.
+fn http_version_label(version: reqwest::Version) -> &'static str {
+    match version {
+        reqwest::Version::HTTP_09 => "HTTP/0.9",
+        reqwest::Version::HTTP_10 => "HTTP/1.0",
+        reqwest::Version::HTTP_11 => "HTTP/1.1",
+        reqwest::Version::HTTP_2 => "HTTP/2",
+        reqwest::Version::HTTP_3 => "HTTP/3",
+        _ => "unknown",
+    }
+}
+
+/// Whether the body satisfies every configured assertion; `None` when the target has none.
+fn body_matches(t: &Target, body_regex: Option<&Regex>, body: &str) -> Option<bool> {
+    let substring = t.expected_body_substring.as_deref().map(|needle| body.contains(needle));
+    let regex = body_regex.map(|regex| regex.is_match(body));
+    match (substring, regex) {
+        (None, None) => None,
+        (substring, regex) => Some(substring.unwrap_or(true) && regex.unwrap_or(true)),
+    }
+}
+
+/// Prefix match of the media type, ignoring case and parameters such as `charset`; a missing
+/// header never matches.
+fn content_type_matches(expected: &str, actual: Option<&HeaderValue>) -> bool {
+    actual
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .is_some_and(|media_type| media_type.trim().to_ascii_lowercase().starts_with(&expected.to_ascii_lowercase()))
+}
+
+/// Whether every expected header is present, with its value unless the expected value is empty.
+/// Header lookups ignore case; values must match exactly, by any of the header's occurrences.
+fn expected_headers_match(expected: &HashMap<String, String>, actual: &HeaderMap) -> bool {
+    expected.iter().all(|(name, value)| {
+        let mut values = actual.get_all(name.as_str()).iter();
+        if value.is_empty() {
+            values.next().is_some()
+        } else {
+            values.any(|actual| actual.to_str().is_ok_and(|actual| actual.trim() == value))
+        }
+    })
+}
+
+/// Times a lookup of the URL's host, so slow resolvers show up apart from slow servers. `None`
+/// for IP literals or when the lookup fails (the request itself will then report the error).
+async fn resolve_time(url: &reqwest::Url, timeout: Duration) -> Option<i32> {
+    let host = url.domain()?;
+    let port = url.port_or_known_default().unwrap_or(80);
+    let start = Instant::now();
+    match tokio::time::timeout(timeout, tokio::net::lookup_host((host, port))).await {
+        Ok(Ok(_)) => Some(start.elapsed().as_millis() as i32),
+        Ok(Err(e)) => {
+            warn!(host, error = %e, "DNS lookup failed");
+            None
+        }
+        Err(_) => {
+            warn!(host, "DNS lookup timed out");
+            None
+        }
+    }
+}
+
+/// Drains the response body, decoding it per its `Content-Encoding`, keeping at most
+/// [`MAX_BUFFERED_BODY_BYTES`] and only when the target asserts on its content, and returns it
+/// with the total decoded size. Stops with an error as soon as the decoded body exceeds
+/// `max_bytes`, so a small compressed body can't expand without bound.
+async fn read_body(resp: reqwest::Response, t: &Target, max_bytes: u64) -> Result<(Vec<u8>, u64), ProbeError> {
+    let keep = t.expected_body_substring.is_some() || t.expected_body_regex.is_some();
+    let encoding = content_encoding(resp.headers());
+    let chunks = stream::unfold(Some(resp), |resp| async move {
+        let mut resp = resp?;
+        match resp.chunk().await {
+            Ok(Some(chunk)) => Some((Ok(chunk), Some(resp))),
+            Ok(None) => None,
+            Err(e) => Some((Err(std::io::Error::other(e)), None)),
+        }
+    });
+    let raw = StreamReader::new(Box::pin(chunks));
+    // Unknown or stacked encodings are drained as they are
+    let mut decoded: Pin<Box<dyn AsyncRead + Send>> = match encoding.as_deref() {
+        Some("gzip" | "x-gzip") => Box::pin(GzipDecoder::new(raw)),
+        Some("br") => Box::pin(BrotliDecoder::new(raw)),
+        Some("deflate") => Box::pin(ZlibDecoder::new(raw)),
+        _ => Box::pin(raw),
+    };
+
+    let mut body = Vec::new();
+    let mut total = 0u64;
+    let mut buf = vec![0; 16 * 1024];
+    loop {
+        match decoded.read(&mut buf).await {
+            Ok(0) => break,
+            Ok(read) => {
+                total += read as u64;
+                if total > max_bytes {
+                    return Err(ProbeError::BodyTooLarge(max_bytes));
+                }
+                if keep && body.len() < MAX_BUFFERED_BODY_BYTES {
+                    let take = read.min(MAX_BUFFERED_BODY_BYTES - body.len());
+                    body.extend_from_slice(&buf[..take]);
+                }
+            }
+            Err(e) => {
+                warn!(target = %t.log_host(), error = %e, "failed to read response body");
+                break;
+            }
+        }
+    }
+    Ok((body, total))
+}
+
+/// The response's `Content-Encoding`, lower-cased; `None` for an unencoded response.
+fn content_encoding(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(header::CONTENT_ENCODING)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.trim().to_ascii_lowercase())
+        .filter(|value| !value.is_empty() && value != "identity")
+}
+
+/// Resolves a redirect response's `Location` header against the URL that produced it.
+fn redirect_location(resp: &reqwest::Response) -> Option<String> {
+    let location = resp.headers().get(header::LOCATION)?.to_str().ok()?;
+    resp.url().join(location).ok().map(String::from)
+}
+
+/// Builds the target's custom headers, logging and skipping any that are not valid HTTP.
+fn custom_headers(t: &Target) -> HeaderMap {
+    let mut map = HeaderMap::new();
+    let Some(SqlJson(headers)) = &t.headers else {
+        return map;
+    };
+    for (name, value) in headers {
+        match (HeaderName::from_bytes(name.as_bytes()), HeaderValue::from_str(value)) {
+            (Ok(name), Ok(value)) => {
+                map.insert(name, value);
+            }
+            _ => warn!(target_id = t.id, header = %name, "skipping invalid custom header"),
+        }
+    }
+    map
+}
+
+/// Opens a TCP connection to `host:port` over the target's address family, reporting success as
+/// a synthetic status with the connect time, name resolution included.
+async fn probe_tcp(t: &Target, timeout: Duration) -> Result<CheckOutcome, ProbeError> {
+    let start = Instant::now();
+    let connect = async {
+        let addrs: Vec<SocketAddr> =
+            tokio::net::lookup_host(&t.url).await?.filter(|addr| t.ip_family.allows(addr.ip())).collect();
+        if addrs.is_empty() {
+            let msg = format!("no {} addresses found", t.ip_family.as_str());
+            return Err(std::io::Error::new(std::io::ErrorKind::NotFound, msg));
+        }
+        TcpStream::connect(addrs.as_slice()).await
+    };
+    let stream = tokio::time::timeout(timeout, connect).await.map_err(|_| ProbeError::Timeout)??;
+    let latency_ms = start.elapsed().as_millis() as i32;
+    let resolved_ip = stream.peer_addr().ok().map(|addr| addr.ip().to_string());
+    drop(stream);
+    Ok(CheckOutcome { status: Some(SYNTHETIC_OK_STATUS), latency_ms: Some(latency_ms), resolved_ip, ..Default::default() })
+}
+
+/// Sends an ICMP echo request, recording the round-trip time with a synthetic OK status.
+async fn probe_ping(t: &Target, timeout: Duration) -> Result<CheckOutcome, ProbeError> {
+    let (rtt, ip) = ping::echo(&t.url, t.ip_family, timeout).await?;
+    Ok(CheckOutcome {
+        status: Some(SYNTHETIC_OK_STATUS),
+        latency_ms: Some(rtt.as_millis() as i32),
+        resolved_ip: Some(ip.to_string()),
+        ..Default::default()
+    })
+}
+
+/// Why a single probe attempt failed.
+#[derive(Debug)]
+enum ProbeError {
+    Http(reqwest::Error),
+    Connect(std::io::Error),
+    Ping(ping::PingError),
+    Timeout,
+    TooManyRedirects,
+    /// The response body exceeded `MAX_BODY_BYTES`, given here.
+    BodyTooLarge(u64),
+    /// A sequence step, by 0-based index, failed.
+    Step { index: usize, cause: Box<ProbeError> },
+    /// A sequence step returned a status other than the one it expects.
+    UnexpectedStatus(i32),
+    /// A sequence step's response lacked a value it captures, by capture name.
+    MissingCapture(String),
+    /// A sequence target has no steps to run.
+    NoSteps,
+}
+
+impl ProbeError {
+    fn is_timeout(&self) -> bool {
+        match self {
+            ProbeError::Http(e) => e.is_timeout(),
+            ProbeError::Ping(e) => e.is_timeout(),
+            ProbeError::Step { cause, .. } => cause.is_timeout(),
+            ProbeError::Timeout => true,
+            ProbeError::Connect(_)
+            | ProbeError::TooManyRedirects
+            | ProbeError::BodyTooLarge(_)
+            | ProbeError::UnexpectedStatus(_)
+            | ProbeError::MissingCapture(_)
+            | ProbeError::NoSteps => false,
+        }
+    }
+
+    /// Whether another attempt could succeed: an oversized body would only be downloaded again,
+    /// and a sequence without steps stays that way.
+    fn is_retryable(&self) -> bool {
+        match self {
+            ProbeError::Step { cause, .. } => cause.is_retryable(),
+            ProbeError::BodyTooLarge(_) | ProbeError::NoSteps => false,
+            _ => true,
+        }
+    }
+
+    /// The failing step of a sequence check, if any.
+    fn failed_step(&self) -> Option<i32> {
+        match self {
+            ProbeError::Step { index, .. } => Some(*index as i32),
+            _ => None,
+        }
+    }
+
+    /// Category stored as `health_checks.error_kind`.
+    fn kind(&self) -> &'static str {
+        match self {
+            ProbeError::Http(e) if e.is_timeout() => "timeout",
+            ProbeError::Http(e) if e.is_connect() => connect_error_kind(e),
+            ProbeError::Http(e) if e.is_body() => "body",
+            ProbeError::Http(e) if e.is_decode() => "decode",
+            ProbeError::Http(e) if e.is_redirect() => "redirect",
+            ProbeError::Http(_) => "request",
+            ProbeError::Connect(_) => "connect",
+            ProbeError::Ping(e) if e.is_timeout() => "timeout",
+            ProbeError::Ping(_) => "ping",
+            ProbeError::Timeout => "timeout",
+            ProbeError::TooManyRedirects => "redirect",
+            ProbeError::BodyTooLarge(_) => "body_too_large",
+            ProbeError::Step { cause, .. } => cause.kind(),
+            ProbeError::UnexpectedStatus(_) => "unexpected_status",
+            ProbeError::MissingCapture(_) => "capture",
+            ProbeError::NoSteps => "config",
+        }
+    }
+}
+
+/// Tells name resolution and TLS handshake failures apart from other connect errors. reqwest
+/// reports all three as connect errors, so this walks the error's sources: the TLS error is a
+/// `native_tls::Error` (possibly inside an `io::Error`), while hyper's resolver failures are
+/// only recognisable by their message.
+fn connect_error_kind(e: &reqwest::Error) -> &'static str {
+    let mut source = std::error::Error::source(e);
+    while let Some(cause) = source {
+        let io_inner = cause.downcast_ref::<std::io::Error>().and_then(std::io::Error::get_ref);
+        let is_tls = |error: &(dyn std::error::Error + 'static)| error.is::<tokio_native_tls::native_tls::Error>();
+        if is_tls(cause) || io_inner.is_some_and(|inner| is_tls(inner)) {
+            return "tls";
+        }
+        if cause.to_string().starts_with("dns error") {
+            return "dns";
+        }
+        source = cause.source();
+    }
+    "connect"
+}
+
+impl std::fmt::Display for ProbeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProbeError::Http(e) => e.fmt(f),
+            ProbeError::Connect(e) => e.fmt(f),
+            ProbeError::Ping(e) => e.fmt(f),
+            ProbeError::Timeout => f.write_str("timed out"),
+            ProbeError::TooManyRedirects => write!(f, "more than {MAX_REDIRECTS} redirects"),
+            ProbeError::BodyTooLarge(max) => write!(f, "response body larger than {max} bytes"),
+            ProbeError::Step { index, cause } => write!(f, "step {index}: {cause}"),
+            ProbeError::UnexpectedStatus(status) => write!(f, "unexpected status {status}"),
+            ProbeError::MissingCapture(name) => write!(f, "no value to capture as '{name}'"),
+            ProbeError::NoSteps => f.write_str("sequence has no steps"),
+        }
+    }
+}
+
+impl From<reqwest::Error> for ProbeError {
+    /// Drops the URL from the error, since it is logged and may carry credentials.
+    fn from(e: reqwest::Error) -> Self {
+        ProbeError::Http(e.without_url())
+    }
+}
+
+impl From<ping::PingError> for ProbeError {
+    fn from(e: ping::PingError) -> Self {
+        ProbeError::Ping(e)
+    }
+}
+
+impl From<std::io::Error> for ProbeError {
+    fn from(e: std::io::Error) -> Self {
+        ProbeError::Connect(e)
+    }
+}
+
+/// Whether a recorded status code meets the target's expectation: any of its status ranges when
+/// set, otherwise its expected status; without either, any 2xx is healthy.
+fn is_healthy(status: Option<i32>, expected_status: Option<i32>, ranges: Option<&StatusRanges>) -> bool {
+    match (status, ranges, expected_status) {
+        (Some(code), Some(ranges), _) => ranges.contains(code),
+        (Some(code), None, Some(expected)) => code == expected,
+        (Some(code), None, None) => (200..300).contains(&code),
+        (None, ..) => false,
+    }
+}
+
+/// Overall verdict for a check: the status must meet expectations and no content assertion
+/// (each `None` when not configured) may have failed. Queries apply the same rule through the
+/// `check_healthy` SQL function.
+fn is_check_healthy(
+    status: Option<i32>,
+    expected_status: Option<i32>,
+    ranges: Option<&StatusRanges>,
+    assertions: &[Option<bool>],
+) -> bool {
+    is_healthy(status, expected_status, ranges) && !assertions.contains(&Some(false))
+}
+
+/// Registers help text for the per-target metrics exposed at `/metrics`.
+fn describe_metrics() {
+    metrics::describe_gauge!("target_last_status_code", "HTTP status code of the latest check (0 when the request failed)");
+    metrics::describe_gauge!("target_last_response_time_ms", "Response time of the latest successful check in milliseconds");
+    metrics::describe_counter!("target_checks_total", "Total number of checks performed");
+    metrics::describe_gauge!("worker_db_latency_ms", "Round-trip time of the worker's latest SELECT 1 against the database in milliseconds");
+    metrics::describe_counter!("target_check_failures_total", "Total number of checks that failed, did not return the expected status, or failed a content assertion");
+}
+
+/// Updates the Prometheus series for one target after a check.
+fn record_check_metrics(t: &Target, status: Option<i32>, latency_ms: Option<i32>, healthy: bool) {
+    let labels = [("target_id", t.id.to_string()), ("url", t.url.clone())];
+
+    metrics::gauge!("target_last_status_code", &labels).set(status.unwrap_or(0) as f64);
+    if let Some(ms) = latency_ms {
+        metrics::gauge!("target_last_response_time_ms", &labels).set(ms as f64);
+    }
+    metrics::counter!("target_checks_total", &labels).increment(1);
+    if !healthy {
+        metrics::counter!("target_check_failures_total", &labels).increment(1);
+    }
+}
+
+/// Effective check interval for a target, falling back to `default` for missing or non-positive values.
+fn check_interval(target: &Target, default: Duration) -> Duration {
+    target
+        .check_interval_secs
+        .and_then(|s| u64::try_from(s).ok())
+        .filter(|s| *s > 0)
+        .map_or(default, Duration::from_secs)
+}
+
+/// When a target checked (or, for cron targets, first seen) at `last` is next due: its cron
+/// schedule's next time after `last`, or else `last` plus its check interval.
+fn next_due(target: &Target, last: Instant, default_interval: Duration) -> Instant {
+    let schedule = target.cron_schedule.as_deref().and_then(|raw| match cron::Schedule::from_str(raw) {
+        Ok(schedule) => Some(schedule),
+        Err(e) => {
+            // Schedules are validated on save, so this only happens for rows edited by hand
+            warn!(target = %target.log_host(), error = %e, "invalid cron_schedule, using the check interval");
+            None
+        }
+    });
+    let Some(schedule) = schedule else {
+        return last + check_interval(target, default_interval);
+    };
+    // Schedules are in wall-clock time, so map `last` onto it and the next fire time back
+    let now = Utc::now();
+    let last_at = now - chrono::Duration::from_std(last.elapsed()).unwrap_or(chrono::Duration::zero());
+    match schedule.after(&last_at).next() {
+        Some(fires_at) => Instant::now() + (fires_at - now).to_std().unwrap_or_default(),
+        // Never again: park the target a full interval at a time
+        None => last + check_interval(target, default_interval),
+    }
+}
+
+/// Effective request timeout for a target, falling back to the default for missing or non-positive values.
+fn request_timeout(target: &Target) -> Duration {
+    let ms = target
+        .timeout_ms
+        .and_then(|ms| u64::try_from(ms).ok())
+        .filter(|ms| *ms > 0)
+        .unwrap_or(DEFAULT_TIMEOUT_MS);
+    Duration::from_millis(ms)
+}
+
+// --------- Retention ---------
+
+/// How often old health checks are purged.
+const RETENTION_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Maximum rows removed per `DELETE`, keeping each statement's locks short-lived.
+const RETENTION_BATCH_SIZE: i64 = 5_000;
+
+/// Hourly task deleting `health_checks` rows older than the retention period.
+fn start_retention_worker(state: AppState, retention_days: i64, shutdown: CancellationToken) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            match purge_old_checks(&state.pool, retention_days).await {
+                Ok(deleted) => info!(deleted, retention_days, "retention run complete"),
+                Err(e) => error!(error = %e, "retention run failed"),
+            }
+            // One sample per worker tick, so a single statement stays small
+            if let Err(e) = sqlx::query("DELETE FROM db_latency_samples WHERE checked_at < NOW() - ($1 * INTERVAL '1 day')")
+                .bind(retention_days)
+                .execute(&state.pool)
+                .await
+            {
+                error!(error = %e, "failed to purge database latency samples");
+            }
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = sleep(RETENTION_INTERVAL) => {}
+            }
+        }
+    })
+}
+
+/// Deletes expired checks in batches, returning the total number of rows removed.
+async fn purge_old_checks(pool: &PgPool, retention_days: i64) -> anyhow::Result<u64> {
+    let mut deleted = 0;
+    loop {
+        let batch = sqlx::query(
+            r#"
+            DELETE FROM health_checks
+            WHERE id IN (
+                SELECT id FROM health_checks
+                WHERE checked_at < NOW() - ($1 * INTERVAL '1 day')
+                LIMIT $2
+            )
+            "#,
+        )
+        .bind(retention_days)
+        .bind(RETENTION_BATCH_SIZE)
+        .execute(pool)
+        .await?
+        .rows_affected();
+
+        deleted += batch;
+        if batch < RETENTION_BATCH_SIZE as u64 {
+            return Ok(deleted);
+        }
+    }
+}
+
+// --------- Shuttle entrypoint ---------
+
+/// Axum service that shuts down gracefully: on SIGTERM or ctrl-c it stops accepting requests,
+/// stops the background tasks, waits for the worker's current tick, and closes the pools.
+pub struct MonitorService {
+    router: Router,
+    pools: db::Pools,
+    shutdown: CancellationToken,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+#[shuttle_runtime::async_trait]
+impl shuttle_runtime::Service for MonitorService {
+    async fn bind(mut self, addr: SocketAddr) -> Result<(), shuttle_runtime::Error> {
+        let listener = TcpListener::bind(addr).await.map_err(shuttle_runtime::CustomError::new)?;
+        // Connect info gives the rate limiter a fallback client address when there's no proxy header
+        axum::serve(listener, self.router.into_make_service_with_connect_info::<SocketAddr>())
+            .with_graceful_shutdown(shutdown_signal())
+            .await
+            .map_err(shuttle_runtime::CustomError::new)?;
+
+        info!("shutting down background tasks");
+        self.shutdown.cancel();
+        for task in self.tasks {
+            if let Err(e) = task.await {
+                error!(error = %e, "background task ended abnormally");
+            }
+        }
+        self.pools.close().await;
+        info!("shutdown complete");
+
+        Ok(())
+    }
+}
+
+/// Resolves on ctrl-c or, on Unix, SIGTERM.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            error!(error = %e, "failed to listen for ctrl-c");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                error!(error = %e, "failed to listen for SIGTERM");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+    info!("shutdown signal received");
+}
+
+/// Applies the pending migrations in `migrations/`, recording each applied version in `_sqlx_migrations`.
+pub async fn ensure_schema(pool: &PgPool) -> Result<(), sqlx::migrate::MigrateError> {
+    sqlx::migrate!().run(pool).await
+}
+
+/// Span for one API request, carrying the `X-Request-Id` set by [`SetRequestIdLayer`].
+fn request_span(request: &axum::http::Request<axum::body::Body>) -> Span {
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|id| id.to_str().ok())
+        .unwrap_or_default();
+    info_span!("request", method = %request.method(), uri = %request.uri(), request_id)
+}
+
+/// Builds the service: connects to the database, ensures the schema, applies seed/config targets,
+/// builds the Axum router, and launches the background workers.
+///
+/// - Opens separate connection pools for the API and the workers (see [`db::Pools`]).
+/// - Applies database migrations via [`ensure_schema`].
+/// - Spawns Tokio tasks that periodically check targets and purge old results.
+/// - Returns the Axum `Router` wrapped in a [`MonitorService`] that Shuttle runs with graceful shutdown.
+pub async fn init(database_url: &str) -> Result<MonitorService, shuttle_runtime::Error> {
+    let pools = db::Pools::connect_from_env(database_url)
+        .await
+        .map_err(|e| shuttle_runtime::CustomError::msg(format!("invalid database configuration: {e:#}")))?;
+    let pool = pools.api.clone();
+
+    ensure_schema(&pool)
+        .await
+        .map_err(|e| shuttle_runtime::CustomError::msg(format!("failed to run database migrations: {e}")))?;
+
+    // Seeding finishes before the router is handed to Shuttle, so the first requests already
+    // see every target. Optional: seed initial targets from `SEED_URLS` secret (comma-separated)
+    let mut seeded = 0;
+    if let Ok(seed) = std::env::var("SEED_URLS") {
+        for url in seed.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()) {
+            match sqlx::query("INSERT INTO targets (url) VALUES ($1) ON CONFLICT DO NOTHING")
+                .bind(url)
+                .execute(&pool)
+                .await
+            {
+                Ok(result) => seeded += result.rows_affected(),
+                Err(e) => error!(%url, error = %e, "failed to seed target"),
+            }
+        }
+    }
+
+    // Optional: declarative targets from the TOML file at `TARGETS_CONFIG`
+    if let Ok(path) = std::env::var("TARGETS_CONFIG") {
+        targets_file::apply(&pool, std::path::Path::new(&path))
+            .await
+            .map_err(|e| shuttle_runtime::CustomError::msg(format!("invalid targets config: {e:#}")))?;
+    }
+
+    match sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM targets").fetch_one(&pool).await {
+        Ok(total) => info!(seeded, targets = total, "seeding complete: {total} targets"),
+        Err(e) => error!(error = %e, "failed to count targets after seeding"),
+    }
+
+    let worker_config = WorkerConfig::from_env()
+        .map_err(|e| shuttle_runtime::CustomError::msg(format!("invalid worker configuration: {e}")))?;
+
+    let metrics = PrometheusBuilder::new()
+        .install_recorder()
+        .map_err(|e| shuttle_runtime::CustomError::msg(format!("failed to install metrics recorder: {e}")))?;
+    describe_metrics();
+
+    let retention_days = worker_config.retention_days;
+    let state = AppState::new(pool.clone(), metrics, worker_config)
+        .map_err(|e| shuttle_runtime::CustomError::msg(format!("failed to set up application state: {e}")))?;
+
+    // CORS for frontend on Vercel and local dev
+    let cors = cors::layer_from_env()
+        .map_err(|e| shuttle_runtime::CustomError::msg(format!("invalid CORS configuration: {e}")))?;
+
+    // Mutating routes require the `X-Api-Key` header; reads stay public
+    let writes = Router::new()
+        .route("/api/targets", post(create_target))
+        .route("/api/targets/bulk", post(bulk_create_targets))
+        .route("/api/targets/validate", post(validate_target))
+        .route("/api/targets/:target_id", patch(update_target).delete(delete_target))
+        .route("/api/targets/:target_id/pause", post(pause_target))
+        .route("/api/targets/:target_id/resume", post(resume_target))
+        .route("/api/targets/:target_id/check", post(check_target_now))
+        .route("/api/check-all", post(check_all))
+        .route("/api/targets/:target_id/maintenance", post(create_maintenance_window))
+        .route("/api/targets/:target_id/maintenance/:window_id", delete(delete_maintenance_window))
+        .route("/api/status/:target_id", delete(clear_history))
+        .route("/api/status/:target_id/import", post(import_history))
+        .route("/api/maintenance/on", post(service_maintenance_on))
+        .route("/api/maintenance/off", post(service_maintenance_off))
+        .route("/api/alert-routes", get(list_alert_routes).post(create_alert_route))
+        .route("/api/alert-routes/:route_id", delete(delete_alert_route))
+        .route_layer(middleware::from_fn_with_state(ApiKey::from_env(), require_api_key));
+
+    let rate_limiter = RateLimiter::from_env()
+        .map_err(|e| shuttle_runtime::CustomError::msg(format!("invalid rate limit configuration: {e}")))?;
+
+    // Public reads are rate limited per client IP; probes and metrics scrapes are not
+    let reads = Router::new()
+        .route("/api/targets", get(list_targets))
+        .route("/api/targets/:target_id/cert", get(get_target_cert))
+        .route("/api/targets/:target_id/flapping", get(get_flapping))
+        .route("/api/status/:target_id/burnrate", get(get_burn_rate))
+        .route("/api/targets/:target_id/maintenance", get(list_maintenance_windows))
+        .route("/api/targets/:target_id/deploys", get(list_deploys))
+        .route("/api/status", get(get_batch_status))
+        .route("/api/status/overall", get(get_overall_status))
+        .route("/api/uptime", get(get_tag_uptime))
+        .route("/api/public/status", get(get_public_status))
+        .route("/api/status/:target_id", get(get_status))
+        .route("/api/status/:target_id/uptime", get(get_uptime))
+        .route("/api/status/:target_id/latency", get(get_latency))
+        .route("/api/status/:target_id/anomalies", get(get_anomalies))
+        .route("/api/status/:target_id/codes", get(get_status_codes))
+        .route("/api/status/:target_id/timeseries", get(get_timeseries))
+        .route("/api/status/:target_id/export.csv", get(export_status_csv))
+        .route("/api/status/:target_id/incidents", get(get_incidents))
+        .route("/api/dashboard", get(get_dashboard))
+        .route("/api/live", get(live_feed))
+        .route("/api/events", get(event_stream))
+        .route("/api/worker", get(get_worker_status))
+        .route("/api/worker/stats", get(get_worker_stats))
+        .route("/api/worker/db-latency", get(get_db_latency))
+        .route("/api/check-all/:job_id", get(get_sweep))
+        .route("/api/alerts", get(list_alerts))
+        .merge(graphql::routes(pool.clone()))
+        .route_layer(middleware::from_fn_with_state(rate_limiter, rate_limit));
+
+    let app = Router::new()
+        .route("/health", get(health))
+        .route("/ready", get(ready))
+        .route("/metrics", get(prometheus_metrics))
+        // Authenticated by its body signature rather than the API key, so CI needs no API key
+        .route("/api/targets/:target_id/deployed", post(record_deploy))
+        .route("/api-docs/openapi.json", get(openapi::openapi_json))
+        .route("/swagger", get(openapi::swagger_ui))
+        .merge(reads)
+        .merge(writes)
+        .with_state(state.clone())
+        // Innermost so traces and CORS headers apply to the compressed response; streamed
+        // bodies such as the CSV export have no known size and are always compressed
+        .layer(CompressionLayer::new().compress_when(DefaultPredicate::new().and(SizeAbove::new(COMPRESSION_MIN_BYTES))))
+        .layer(TraceLayer::new_for_http().make_span_with(request_span))
+        // Outside the trace layer so its span sees the id; a client-supplied `X-Request-Id` is kept
+        .layer(PropagateRequestIdLayer::new(REQUEST_ID_HEADER))
+        .layer(SetRequestIdLayer::new(REQUEST_ID_HEADER, MakeRequestUuid))
+        .layer(cors);
+
+    // Start background workers on their own pool; on-demand checks keep using the API's
+    let worker_state = AppState { pool: pools.worker.clone(), ..state };
+    let shutdown = CancellationToken::new();
+    let retention = start_retention_worker(worker_state.clone(), retention_days, shutdown.clone());
+    let worker = start_background_worker(worker_state, shutdown.clone());
+
+    info!("service started");
+
+    Ok(MonitorService { router: app, pools, shutdown, tasks: vec![worker, retention] })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_window_accepts_minutes_hours_and_days() {
+        assert_eq!(parse_window("30m"), Ok(30 * 60));
+        assert_eq!(parse_window(" 24h "), Ok(24 * 60 * 60));
+        assert_eq!(parse_window("7d"), Ok(7 * 24 * 60 * 60));
+    }
+
+    #[test]
+    fn parse_window_rejects_bad_input() {
+        for raw in ["", "24", "h", "0h", "-1d", "1.5h", "1w", "99999999999999999d"] {
+            assert!(parse_window(raw).is_err(), "{raw} should be rejected");
+        }
+    }
+
+    #[test]
+    fn validate_buckets_requires_an_even_split_within_the_limit() {
+        assert!(validate_buckets(3600, 60).is_ok());
+        assert!(validate_buckets(3600, 3600).is_ok());
+        assert!(validate_buckets(3600, 7200).is_err());
+        assert!(validate_buckets(3600, 7).is_err());
+        assert!(validate_buckets(MAX_TIMESERIES_BUCKETS + 1, 1).is_err());
+    }
+
+    #[test]
+    fn is_healthy_prefers_ranges_then_expected_status_then_2xx() {
+        let ranges: StatusRanges = "200-299,301".parse().unwrap();
+        assert!(is_healthy(Some(301), Some(200), Some(&ranges)));
+        assert!(!is_healthy(Some(302), Some(302), Some(&ranges)));
+        assert!(is_healthy(Some(404), Some(404), None));
+        assert!(!is_healthy(Some(200), Some(404), None));
+        assert!(is_healthy(Some(204), None, None));
+        assert!(!is_healthy(Some(301), None, None));
+        assert!(!is_healthy(None, None, None));
+    }
+
+    #[test]
+    fn is_check_healthy_fails_on_any_failed_assertion() {
+        assert!(is_check_healthy(Some(200), None, None, &[None, Some(true), None, None]));
+        assert!(!is_check_healthy(Some(200), None, None, &[Some(true), None, Some(false), None]));
+        assert!(!is_check_healthy(Some(500), None, None, &[Some(true), None, None, None]));
+    }
+
+    #[test]
+    fn validate_content_type_normalizes_media_types() {
+        assert_eq!(validate_content_type(" Application/JSON "), Ok("application/json".to_string()));
+        for raw in ["json", "/json", "text/html; charset=utf-8", "text/\nhtml"] {
+            assert!(validate_content_type(raw).is_err(), "{raw:?} should be rejected");
+        }
+    }
+
+    #[test]
+    fn validate_expected_headers_lowercases_names() {
+        let headers = HashMap::from([("X-Served-By".to_string(), " edge ".to_string())]);
+        let normalized = validate_expected_headers(&headers).unwrap();
+        assert_eq!(normalized, HashMap::from([("x-served-by".to_string(), "edge".to_string())]));
+    }
+
+    #[test]
+    fn validate_expected_headers_rejects_duplicates_and_bad_names() {
+        let duplicate = HashMap::from([("X-Id".to_string(), "a".to_string()), ("x-id".to_string(), "b".to_string())]);
+        assert!(validate_expected_headers(&duplicate).is_err());
+        let bad_name = HashMap::from([("bad header".to_string(), "a".to_string())]);
+        assert!(validate_expected_headers(&bad_name).is_err());
+        let bad_value = HashMap::from([("x-id".to_string(), "a\nb".to_string())]);
+        assert!(validate_expected_headers(&bad_value).is_err());
+    }
+
+    #[test]
+    fn validate_cron_schedule_adds_seconds_to_five_field_expressions() {
+        assert_eq!(validate_cron_schedule(" */5 * * * * "), Ok("0 */5 * * * *".to_string()));
+        assert_eq!(validate_cron_schedule("0 30 9 * * Mon,Wed,Fri"), Ok("0 30 9 * * Mon,Wed,Fri".to_string()));
+    }
+
+    #[test]
+    fn validate_cron_schedule_rejects_bad_or_past_schedules() {
+        assert!(validate_cron_schedule("not a schedule").is_err());
+        assert!(validate_cron_schedule("0 0 0 1 1 * 2000").is_err());
+    }
+
+    fn entry(id: i32, tags: &[&str]) -> DashboardEntry {
+        DashboardEntry {
+            id,
+            url: format!("https://example.com/{id}"),
+            check_type: CheckType::Http,
+            enabled: true,
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            consecutive_failures: 0,
+            last_checked_at: None,
+            last_status_code: None,
+            last_response_time_ms: None,
+            latency_ema_ms: None,
+            healthy: None,
+            slow: false,
+            status_since: None,
+            status_duration_secs: None,
+            checks_24h: 0,
+            uptime_pct_24h: None,
+            regions: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn group_by_tag_lists_targets_under_each_tag() {
+        let groups = group_by_tag(vec![entry(1, &["api", "prod"]), entry(2, &["prod"]), entry(3, &[])]);
+        let ids = |group: &str| groups[group].iter().map(|entry| entry.id).collect::<Vec<_>>();
+        assert_eq!(groups.keys().collect::<Vec<_>>(), ["api", "prod", UNTAGGED_GROUP]);
+        assert_eq!(ids("api"), [1]);
+        assert_eq!(ids("prod"), [1, 2]);
+        assert_eq!(ids(UNTAGGED_GROUP), [3]);
+    }
+}
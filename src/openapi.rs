@@ -0,0 +1,69 @@
+//! OpenAPI description of the HTTP API, generated from the `#[utoipa::path]` annotations on the
+//! handlers and the `ToSchema` derives on their payloads. New handlers are documented by
+//! annotating them and listing them in [`ApiDoc`].
+
+use axum::{response::Html, Json};
+use utoipa::{
+    openapi::security::{ApiKey, ApiKeyValue, SecurityScheme},
+    Modify, OpenApi,
+};
+
+use crate::auth::API_KEY_HEADER;
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        crate::list_targets,
+        crate::create_target,
+        crate::update_target,
+        crate::delete_target,
+        crate::get_status,
+    ),
+    components(schemas(
+        crate::Target,
+        crate::CheckType,
+        crate::CreateTarget,
+        crate::UpdateTarget,
+        crate::HealthCheckRecord,
+        crate::StatusRecord,
+        crate::StatusPage,
+    )),
+    modifiers(&ApiKeyAuth),
+)]
+pub struct ApiDoc;
+
+/// Registers the `X-Api-Key` header required by the mutating endpoints.
+struct ApiKeyAuth;
+
+impl Modify for ApiKeyAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme("api_key", SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new(API_KEY_HEADER))));
+    }
+}
+
+pub async fn openapi_json() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+/// Swagger UI page for the spec; the UI assets are loaded from a CDN rather than bundled.
+pub async fn swagger_ui() -> Html<&'static str> {
+    Html(SWAGGER_UI_PAGE)
+}
+
+const SWAGGER_UI_PAGE: &str = r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8">
+  <title>DevOps Health Monitor API</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css">
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js"></script>
+  <script>
+    window.ui = SwaggerUIBundle({ url: "/api-docs/openapi.json", dom_id: "#swagger-ui" });
+  </script>
+</body>
+</html>
+"##;
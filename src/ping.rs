@@ -0,0 +1,79 @@
+//! ICMP echo checks for hosts without an HTTP or TCP service.
+//!
+//! Opening an ICMP socket usually needs privileges: on Linux either an unprivileged ping socket
+//! (the process's group within `net.ipv4.ping_group_range`) or `CAP_NET_RAW` for a raw socket.
+
+use std::{
+    io,
+    net::IpAddr,
+    time::{Duration, Instant},
+};
+
+use rand::random;
+use surge_ping::{Client, Config, PingIdentifier, PingSequence, SurgeError, ICMP};
+
+use crate::IpFamily;
+
+/// Payload carried by each echo request, the same size `ping` sends by default.
+const PAYLOAD: [u8; 56] = [0; 56];
+
+/// Why an echo request got no reply.
+#[derive(Debug)]
+pub enum PingError {
+    Resolve(io::Error),
+    /// The ICMP socket could not be created, typically for lack of privileges.
+    Socket(io::Error),
+    Echo(SurgeError),
+}
+
+impl PingError {
+    pub fn is_timeout(&self) -> bool {
+        matches!(self, PingError::Echo(SurgeError::Timeout { .. }))
+    }
+}
+
+impl std::fmt::Display for PingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PingError::Resolve(e) => write!(f, "failed to resolve host: {e}"),
+            PingError::Socket(e) => write!(
+                f,
+                "failed to open ICMP socket (requires CAP_NET_RAW or a group within net.ipv4.ping_group_range): {e}"
+            ),
+            PingError::Echo(e) => e.fmt(f),
+        }
+    }
+}
+
+/// Sends one echo request to `host` (a host name or IP address) over `family` and returns the
+/// round-trip time with the address pinged. Name resolution counts towards `timeout`.
+pub async fn echo(host: &str, family: IpFamily, timeout: Duration) -> Result<(Duration, IpAddr), PingError> {
+    let start = Instant::now();
+    let ip = resolve(host, family, timeout).await?;
+
+    let config = match ip {
+        IpAddr::V4(_) => Config::default(),
+        IpAddr::V6(_) => Config::builder().kind(ICMP::V6).build(),
+    };
+    let client = Client::new(&config).map_err(PingError::Socket)?;
+    let mut pinger = client.pinger(ip, PingIdentifier(random())).await;
+    pinger.timeout(timeout.saturating_sub(start.elapsed()));
+    let (_, rtt) = pinger.ping(PingSequence(0), &PAYLOAD).await.map_err(PingError::Echo)?;
+    Ok((rtt, ip))
+}
+
+/// The first of the host's addresses in `family`; an IP literal of the other family is not found.
+async fn resolve(host: &str, family: IpFamily, timeout: Duration) -> Result<IpAddr, PingError> {
+    let not_found = || PingError::Resolve(io::Error::new(io::ErrorKind::NotFound, "no addresses found"));
+    if let Ok(ip) = host.parse() {
+        return family.allows(ip).then_some(ip).ok_or_else(not_found);
+    }
+    let lookup = tokio::time::timeout(timeout, tokio::net::lookup_host((host, 0)))
+        .await
+        .map_err(|_| PingError::Resolve(io::Error::new(io::ErrorKind::TimedOut, "lookup timed out")))?;
+    lookup
+        .map_err(PingError::Resolve)?
+        .map(|addr| addr.ip())
+        .find(|ip| family.allows(*ip))
+        .ok_or_else(not_found)
+}
@@ -0,0 +1,149 @@
+//! Per-client rate limiting for the public read endpoints.
+
+use std::{
+    net::{IpAddr, SocketAddr},
+    num::NonZeroU32,
+    sync::Arc,
+};
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header, HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use governor::{
+    clock::{Clock, DefaultClock},
+    DefaultKeyedRateLimiter, Quota,
+};
+use tracing::{info, warn};
+
+/// Client addresses tracked before idle entries are pruned from the limiter.
+const PRUNE_THRESHOLD: usize = 10_000;
+
+/// Token-bucket limiter keyed by client IP.
+#[derive(Clone)]
+pub struct RateLimiter {
+    limiter: Arc<DefaultKeyedRateLimiter<IpAddr>>,
+    clock: DefaultClock,
+    /// Proxies in front of the service that append to `X-Forwarded-For`.
+    trusted_hops: usize,
+}
+
+impl RateLimiter {
+    /// Allows `RATE_LIMIT_PER_MIN` requests per minute per client IP (default 120), reading the
+    /// client from `X-Forwarded-For` behind `TRUSTED_PROXY_HOPS` proxies (default 1, Shuttle's).
+    pub fn from_env() -> anyhow::Result<Self> {
+        let per_min: u32 = crate::env_or("RATE_LIMIT_PER_MIN", 120)?;
+        let per_min = NonZeroU32::new(per_min).ok_or_else(|| anyhow::anyhow!("RATE_LIMIT_PER_MIN must be greater than zero"))?;
+        let trusted_hops: usize = crate::env_or("TRUSTED_PROXY_HOPS", 1)?;
+        anyhow::ensure!(trusted_hops > 0, "TRUSTED_PROXY_HOPS must be greater than zero");
+        info!(per_min, trusted_hops, "rate limiting read endpoints");
+        Ok(Self {
+            limiter: Arc::new(governor::RateLimiter::keyed(Quota::per_minute(per_min))),
+            clock: DefaultClock::default(),
+            trusted_hops,
+        })
+    }
+}
+
+/// Middleware answering `429 Too Many Requests` with `Retry-After` once a client exceeds its quota.
+pub async fn rate_limit(State(limiter): State<RateLimiter>, req: Request, next: Next) -> Response {
+    let Some(ip) = client_ip(&req, limiter.trusted_hops) else {
+        // Without an address there is nothing to key on; let the request through
+        return next.run(req).await;
+    };
+
+    if limiter.limiter.len() > PRUNE_THRESHOLD {
+        limiter.limiter.retain_recent();
+    }
+
+    if let Err(not_until) = limiter.limiter.check_key(&ip) {
+        let wait = not_until.wait_time_from(limiter.clock.now());
+        // Round up so clients never retry before a token is available
+        let retry_after = wait.as_secs() + u64::from(wait.subsec_nanos() > 0);
+        warn!(%ip, path = %req.uri().path(), retry_after, "rate limit exceeded");
+        return (
+            StatusCode::TOO_MANY_REQUESTS,
+            [(header::RETRY_AFTER, retry_after.max(1).to_string())],
+            "rate limit exceeded",
+        )
+            .into_response();
+    }
+    next.run(req).await
+}
+
+/// The originating client as seen by the outermost trusted proxy (see [`forwarded_for`]), or
+/// the peer address when there is no `X-Forwarded-For` header.
+fn client_ip(req: &Request, trusted_hops: usize) -> Option<IpAddr> {
+    match req.headers().get("x-forwarded-for") {
+        Some(_) => forwarded_for(req.headers(), trusted_hops),
+        None => req
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip()),
+    }
+}
+
+/// The `X-Forwarded-For` entry `trusted_hops` from the right: each trusted proxy appends the
+/// address it received the request from, so entries further left are client-supplied and can't
+/// be trusted. With fewer entries than hops, every entry was added by a proxy and the leftmost
+/// is the client.
+fn forwarded_for(headers: &HeaderMap, trusted_hops: usize) -> Option<IpAddr> {
+    let entries: Vec<&str> = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .collect();
+    let index = entries.len().saturating_sub(trusted_hops);
+    entries.get(index)?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderValue;
+
+    use super::*;
+
+    fn headers(values: &[&str]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for value in values {
+            headers.append("x-forwarded-for", HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn forwarded_for_takes_the_entry_appended_by_the_trusted_proxy() {
+        let headers = headers(&["1.1.1.1, 2.2.2.2, 203.0.113.7"]);
+        assert_eq!(forwarded_for(&headers, 1), Some("203.0.113.7".parse().unwrap()));
+        assert_eq!(forwarded_for(&headers, 2), Some("2.2.2.2".parse().unwrap()));
+    }
+
+    #[test]
+    fn forwarded_for_ignores_spoofed_leading_entries() {
+        let spoofed = headers(&["10.0.0.1, 203.0.113.7"]);
+        let other = headers(&["10.0.0.2, 203.0.113.7"]);
+        assert_eq!(forwarded_for(&spoofed, 1), forwarded_for(&other, 1));
+    }
+
+    #[test]
+    fn forwarded_for_joins_repeated_headers() {
+        let headers = headers(&["1.1.1.1", "203.0.113.7"]);
+        assert_eq!(forwarded_for(&headers, 1), Some("203.0.113.7".parse().unwrap()));
+    }
+
+    #[test]
+    fn forwarded_for_uses_the_leftmost_entry_with_fewer_entries_than_hops() {
+        let headers = headers(&["203.0.113.7"]);
+        assert_eq!(forwarded_for(&headers, 2), Some("203.0.113.7".parse().unwrap()));
+    }
+
+    #[test]
+    fn forwarded_for_rejects_garbage() {
+        assert_eq!(forwarded_for(&headers(&["not-an-ip"]), 1), None);
+        assert_eq!(forwarded_for(&HeaderMap::new(), 1), None);
+    }
+}
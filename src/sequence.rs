@@ -0,0 +1,268 @@
+//! Multi-step checks (`check_type = "sequence"`) that walk a user journey such as logging in and
+//! then loading a protected page. Values captured from one step's response are substituted into
+//! later steps through `{{name}}` placeholders.
+
+use std::{
+    collections::{HashMap, HashSet},
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+use reqwest::header::{HeaderName, HeaderValue};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{CheckOutcome, ProbeError, Target, REQUEST_ID_HEADER, SYNTHETIC_OK_STATUS};
+
+/// Most steps accepted in one sequence.
+const MAX_STEPS: usize = 10;
+
+/// One request of a sequence.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SequenceStep {
+    /// Request method, `GET` when omitted.
+    pub method: Option<String>,
+    /// Absolute, or relative to the target's `url`.
+    pub url: String,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    pub body: Option<String>,
+    /// Status the step must return; any 2xx when omitted.
+    pub expected_status: Option<i32>,
+    /// Values taken from the response, by placeholder name.
+    #[serde(default)]
+    pub capture: HashMap<String, Capture>,
+}
+
+/// Where a captured value comes from.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Capture {
+    /// A response header, by name.
+    Header(String),
+    /// A JSON pointer such as `/data/token` into the response body.
+    JsonPointer(String),
+}
+
+/// Checks a sequence's steps, normalizing each method, and that every placeholder refers to a
+/// value captured by an earlier step. Returns a message suitable for a `400` otherwise.
+pub fn validate_steps(steps: &mut [SequenceStep], base: &str) -> Result<(), String> {
+    if steps.is_empty() || steps.len() > MAX_STEPS {
+        return Err(format!("a sequence needs between 1 and {MAX_STEPS} steps"));
+    }
+    let base = reqwest::Url::parse(base).map_err(|e| format!("invalid url '{base}': {e}"))?;
+    let mut captured = HashSet::<String>::new();
+    for (index, step) in steps.iter_mut().enumerate() {
+        let invalid = |reason: String| format!("invalid step {index}: {reason}");
+        let method = crate::validate_http_method(step.method.as_deref().unwrap_or("GET"), step.body.as_deref())
+            .map_err(invalid)?;
+        step.method = Some(method);
+
+        let templates = std::iter::once(&step.url).chain(step.headers.values()).chain(step.body.as_ref());
+        for name in templates.flat_map(|template| placeholders(template)) {
+            if !captured.contains(name) {
+                return Err(invalid(format!("placeholder '{{{{{name}}}}}' is not captured by an earlier step")));
+            }
+        }
+        // Placeholders are filled in at check time, so check the URL with a stand-in value
+        let url = placeholders(&step.url).fold(step.url.clone(), |url, name| url.replace(&format!("{{{{{name}}}}}"), "x"));
+        let url = base.join(&url).map_err(|e| invalid(format!("invalid url '{}': {e}", step.url)))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(invalid(format!("url '{}' must use http or https", step.url)));
+        }
+        for name in step.headers.keys() {
+            HeaderName::from_bytes(name.as_bytes()).map_err(|e| invalid(format!("invalid header name '{name}': {e}")))?;
+        }
+        if step.expected_status.is_some_and(|status| !(100..=599).contains(&status)) {
+            return Err(invalid("expected_status must be a valid HTTP status code (100-599)".into()));
+        }
+        for name in step.capture.keys() {
+            if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                return Err(invalid(format!("capture name '{name}' may only contain letters, digits, and underscores")));
+            }
+        }
+        captured.extend(step.capture.keys().cloned());
+    }
+    Ok(())
+}
+
+/// Names of the `{{name}}` placeholders in `template`.
+fn placeholders(template: &str) -> impl Iterator<Item = &str> {
+    template
+        .split("{{")
+        .skip(1)
+        .filter_map(|rest| rest.split_once("}}").map(|(name, _)| name))
+}
+
+/// Fills in the placeholders of `template` from `values`.
+fn render(template: &str, values: &HashMap<String, String>) -> String {
+    values
+        .iter()
+        .fold(template.to_string(), |rendered, (name, value)| rendered.replace(&format!("{{{{{name}}}}}"), value))
+}
+
+/// Runs the target's steps in order, each with the target's timeout. The whole sequence is
+/// reported as a synthetic OK status with its total time when every step passes; the first
+/// failing step ends it with a [`ProbeError::Step`] carrying its index.
+pub async fn probe(
+    client: &reqwest::Client,
+    t: &Target,
+    timeout: Duration,
+    request_id: Uuid,
+    max_body_bytes: u64,
+) -> Result<CheckOutcome, ProbeError> {
+    let steps = t.steps.as_ref().map(|steps| steps.0.as_slice()).unwrap_or_default();
+    if steps.is_empty() {
+        return Err(ProbeError::NoSteps);
+    }
+    let base = reqwest::Url::parse(&t.url).ok();
+    let mut values = HashMap::new();
+    let mut remote_addr = None;
+    let start = Instant::now();
+    for (index, step) in steps.iter().enumerate() {
+        remote_addr = run_step(client, base.as_ref(), step, &mut values, timeout, request_id, max_body_bytes)
+            .await
+            .map_err(|cause| ProbeError::Step { index, cause: Box::new(cause) })?;
+    }
+    Ok(CheckOutcome {
+        status: Some(SYNTHETIC_OK_STATUS),
+        latency_ms: Some(start.elapsed().as_millis() as i32),
+        resolved_ip: remote_addr.map(|addr| addr.ip().to_string()),
+        ..Default::default()
+    })
+}
+
+/// Sends one step and adds its captures to `values`, returning the address the step's response
+/// came from.
+async fn run_step(
+    client: &reqwest::Client,
+    base: Option<&reqwest::Url>,
+    step: &SequenceStep,
+    values: &mut HashMap<String, String>,
+    timeout: Duration,
+    request_id: Uuid,
+    max_body_bytes: u64,
+) -> Result<Option<SocketAddr>, ProbeError> {
+    let url = render(&step.url, values);
+    // An unparseable URL is left for reqwest to reject as a request error
+    let url = base.and_then(|base| base.join(&url).ok()).map_or(url, String::from);
+    let method = step.method.as_deref().unwrap_or("GET");
+    let method = reqwest::Method::from_bytes(method.as_bytes()).unwrap_or(reqwest::Method::GET);
+
+    let mut request = client
+        .request(method, url)
+        .timeout(timeout)
+        .header(REQUEST_ID_HEADER, request_id.to_string());
+    for (name, value) in &step.headers {
+        if let (Ok(name), Ok(value)) = (HeaderName::from_bytes(name.as_bytes()), HeaderValue::from_str(&render(value, values))) {
+            request = request.header(name, value);
+        }
+    }
+    if let Some(body) = &step.body {
+        request = request.body(render(body, values));
+    }
+    let mut resp = request.send().await?;
+    let remote_addr = resp.remote_addr();
+
+    let status = resp.status().as_u16() as i32;
+    if !crate::is_healthy(Some(status), step.expected_status, None) {
+        return Err(ProbeError::UnexpectedStatus(status));
+    }
+
+    let mut captured = Vec::new();
+    for (name, capture) in &step.capture {
+        if let Capture::Header(header) = capture {
+            let value = resp.headers().get(header).and_then(|value| value.to_str().ok());
+            let value = value.ok_or_else(|| ProbeError::MissingCapture(name.clone()))?;
+            captured.push((name.clone(), value.to_string()));
+        }
+    }
+
+    // Always drain the body so the connection can be reused, keeping it only when it is needed
+    let keep = step.capture.values().any(|capture| matches!(capture, Capture::JsonPointer(_)));
+    let mut body = Vec::new();
+    let mut total = 0u64;
+    while let Some(chunk) = resp.chunk().await? {
+        total += chunk.len() as u64;
+        if total > max_body_bytes {
+            return Err(ProbeError::BodyTooLarge(max_body_bytes));
+        }
+        if keep {
+            body.extend_from_slice(&chunk);
+        }
+    }
+
+    if keep {
+        let json = serde_json::from_slice::<serde_json::Value>(&body).ok();
+        for (name, capture) in &step.capture {
+            if let Capture::JsonPointer(pointer) = capture {
+                let value = json.as_ref().and_then(|json| json.pointer(pointer)).map(|value| match value {
+                    serde_json::Value::String(text) => text.clone(),
+                    other => other.to_string(),
+                });
+                let value = value.ok_or_else(|| ProbeError::MissingCapture(name.clone()))?;
+                captured.push((name.clone(), value));
+            }
+        }
+    }
+
+    values.extend(captured);
+    Ok(remote_addr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn steps(json: serde_json::Value) -> Vec<SequenceStep> {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn placeholders_finds_each_name() {
+        let names: Vec<_> = placeholders("/users/{{user_id}}?token={{token}}&x={{").collect();
+        assert_eq!(names, ["user_id", "token"]);
+        assert_eq!(placeholders("/health").count(), 0);
+    }
+
+    #[test]
+    fn render_fills_in_captured_values() {
+        let values = HashMap::from([("token".to_string(), "abc".to_string())]);
+        assert_eq!(render("Bearer {{token}}", &values), "Bearer abc");
+        assert_eq!(render("{{missing}}", &values), "{{missing}}");
+    }
+
+    #[test]
+    fn validate_steps_normalizes_methods() {
+        let mut steps = steps(serde_json::json!([
+            {"url": "/login", "method": "post", "body": "{}", "capture": {"token": {"json_pointer": "/token"}}},
+            {"url": "/me", "headers": {"Authorization": "Bearer {{token}}"}},
+        ]));
+        validate_steps(&mut steps, "https://example.com").unwrap();
+        assert_eq!(steps[0].method.as_deref(), Some("POST"));
+        assert_eq!(steps[1].method.as_deref(), Some("GET"));
+    }
+
+    #[test]
+    fn validate_steps_requires_placeholders_to_be_captured_earlier() {
+        let mut steps = steps(serde_json::json!([
+            {"url": "/items/{{id}}"},
+            {"url": "/items", "capture": {"id": {"json_pointer": "/id"}}},
+        ]));
+        let err = validate_steps(&mut steps, "https://example.com").unwrap_err();
+        assert!(err.starts_with("invalid step 0"), "{err}");
+    }
+
+    #[test]
+    fn validate_steps_rejects_bad_steps() {
+        let base = "https://example.com";
+        assert!(validate_steps(&mut [], base).is_err());
+        assert!(validate_steps(&mut steps(serde_json::json!([{"url": "ftp://example.com/"}])), base).is_err());
+        assert!(validate_steps(&mut steps(serde_json::json!([{"url": "/", "expected_status": 99}])), base).is_err());
+        assert!(validate_steps(&mut steps(serde_json::json!([{"url": "/", "method": "GET", "body": "x"}])), base).is_err());
+        let bad_capture = serde_json::json!([{"url": "/", "capture": {"bad-name": {"header": "x-id"}}}]);
+        assert!(validate_steps(&mut steps(bad_capture), base).is_err());
+        let too_many = serde_json::Value::Array(vec![serde_json::json!({"url": "/"}); MAX_STEPS + 1]);
+        assert!(validate_steps(&mut steps(too_many), base).is_err());
+    }
+}
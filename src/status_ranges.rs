@@ -0,0 +1,119 @@
+//! Accepted status codes for a target, written as a spec such as `200-299,301,418`.
+
+use std::{fmt, ops::RangeInclusive, str::FromStr};
+
+use serde::{Serialize, Serializer};
+use sqlx::{
+    error::BoxDynError,
+    postgres::{PgTypeInfo, PgValueRef},
+    Decode, Postgres, Type,
+};
+
+/// Parsed `expected_status_ranges`. Specs are validated and stored in canonical form, which the
+/// `status_matches` SQL function relies on.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StatusRanges(Vec<RangeInclusive<i32>>);
+
+impl StatusRanges {
+    pub fn contains(&self, code: i32) -> bool {
+        self.0.iter().any(|range| range.contains(&code))
+    }
+}
+
+impl FromStr for StatusRanges {
+    type Err = String;
+
+    /// Comma-separated codes and inclusive `low-high` ranges, each within 100-599.
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| format!("invalid expected_status_ranges '{raw}': {reason}");
+        let parse_code = |code: &str| {
+            code.trim()
+                .parse::<i32>()
+                .ok()
+                .filter(|code| (100..=599).contains(code))
+                .ok_or_else(|| invalid("status codes must be numbers between 100 and 599"))
+        };
+
+        let mut ranges = Vec::new();
+        for part in raw.split(',') {
+            if part.trim().is_empty() {
+                return Err(invalid("empty entry"));
+            }
+            let range = match part.split_once('-') {
+                Some((low, high)) => parse_code(low)?..=parse_code(high)?,
+                None => {
+                    let code = parse_code(part)?;
+                    code..=code
+                }
+            };
+            if range.is_empty() {
+                return Err(invalid("range start is greater than its end"));
+            }
+            ranges.push(range);
+        }
+        Ok(Self(ranges))
+    }
+}
+
+impl fmt::Display for StatusRanges {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, range) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            if range.start() == range.end() {
+                write!(f, "{}", range.start())?;
+            } else {
+                write!(f, "{}-{}", range.start(), range.end())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for StatusRanges {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl Type<Postgres> for StatusRanges {
+    fn type_info() -> PgTypeInfo {
+        <String as Type<Postgres>>::type_info()
+    }
+}
+
+impl<'r> Decode<'r, Postgres> for StatusRanges {
+    fn decode(value: PgValueRef<'r>) -> Result<Self, BoxDynError> {
+        Ok(<&str as Decode<Postgres>>::decode(value)?.parse()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_codes_and_ranges() {
+        let ranges: StatusRanges = " 200-299, 301,418".parse().unwrap();
+        assert!(ranges.contains(200));
+        assert!(ranges.contains(299));
+        assert!(ranges.contains(301));
+        assert!(ranges.contains(418));
+        assert!(!ranges.contains(300));
+        assert!(!ranges.contains(500));
+    }
+
+    #[test]
+    fn displays_in_canonical_form() {
+        let ranges: StatusRanges = " 200-299, 301,418-418".parse().unwrap();
+        assert_eq!(ranges.to_string(), "200-299,301,418");
+    }
+
+    #[test]
+    fn rejects_invalid_specs() {
+        for raw in ["", "200,", "abc", "99", "600", "300-200", "200-", "200-299-300"] {
+            assert!(raw.parse::<StatusRanges>().is_err(), "{raw:?} should be rejected");
+        }
+    }
+}
@@ -0,0 +1,142 @@
+//! Declarative targets loaded from a TOML file at startup (`TARGETS_CONFIG`).
+//!
+//! ```toml
+//! prune = true
+//!
+//! [[targets]]
+//! url = "https://example.com/health"
+//! check_interval_secs = 30
+//! tags = ["prod"]
+//! ```
+
+use std::path::Path;
+
+use anyhow::Context;
+use serde::Deserialize;
+use sqlx::{types::Json as SqlJson, PgPool};
+use tracing::info;
+
+use crate::BulkTarget;
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TargetsFile {
+    /// Disable (never delete) targets in the database that the file does not list.
+    #[serde(default)]
+    prune: bool,
+    #[serde(default)]
+    targets: Vec<BulkTarget>,
+}
+
+/// Parses the file at `path` and upserts its targets, failing on malformed TOML or invalid targets
+/// before anything is written.
+pub async fn apply(pool: &PgPool, path: &Path) -> anyhow::Result<()> {
+    let raw = tokio::fs::read_to_string(path)
+        .await
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    let file: TargetsFile = toml::from_str(&raw).with_context(|| format!("failed to parse {}", path.display()))?;
+
+    let targets = file
+        .targets
+        .into_iter()
+        .enumerate()
+        .map(|(i, target)| {
+            target
+                .validated()
+                .map_err(|e| anyhow::anyhow!("{}: target {}: {e}", path.display(), i + 1))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let mut tx = pool.begin().await?;
+    for target in &targets {
+        // The file is the source of truth for listed targets, so settings it omits are reset
+        // to their defaults and a previously pruned target is re-enabled
+        sqlx::query(
+            r#"
+            INSERT INTO targets (
+                url, check_type, headers, check_interval_secs, timeout_ms, expected_status,
+                follow_redirects, expected_body_substring, http_method, request_body, tags, expected_body_regex,
+                expected_content_type, basic_auth_user, basic_auth_pass, latency_threshold_ms, display_name, max_history,
+                expected_status_ranges, metadata, insecure_skip_tls_verify, steps, expected_headers, cron_schedule, ip_family,
+                expected_content_encoding, enabled
+            )
+            VALUES (
+                $1, $2, $3, $4, $5, COALESCE($6, 200), COALESCE($7, true), $8, COALESCE($9, 'GET'), $10,
+                $11, $12, $13, $14, $15, $16, $17, $18, $19, COALESCE($20, '{}'),
+                $21, $22, $23, $24, $25, $26, true
+            )
+            ON CONFLICT (url) DO UPDATE SET
+                check_type = EXCLUDED.check_type,
+                headers = EXCLUDED.headers,
+                check_interval_secs = EXCLUDED.check_interval_secs,
+                timeout_ms = EXCLUDED.timeout_ms,
+                expected_status = EXCLUDED.expected_status,
+                follow_redirects = EXCLUDED.follow_redirects,
+                expected_body_substring = EXCLUDED.expected_body_substring,
+                http_method = EXCLUDED.http_method,
+                request_body = EXCLUDED.request_body,
+                tags = EXCLUDED.tags,
+                expected_body_regex = EXCLUDED.expected_body_regex,
+                expected_content_type = EXCLUDED.expected_content_type,
+                basic_auth_user = EXCLUDED.basic_auth_user,
+                basic_auth_pass = EXCLUDED.basic_auth_pass,
+                latency_threshold_ms = EXCLUDED.latency_threshold_ms,
+                display_name = EXCLUDED.display_name,
+                max_history = EXCLUDED.max_history,
+                expected_status_ranges = EXCLUDED.expected_status_ranges,
+                metadata = EXCLUDED.metadata,
+                insecure_skip_tls_verify = EXCLUDED.insecure_skip_tls_verify,
+                steps = EXCLUDED.steps,
+                expected_headers = EXCLUDED.expected_headers,
+                cron_schedule = EXCLUDED.cron_schedule,
+                ip_family = EXCLUDED.ip_family,
+                expected_content_encoding = EXCLUDED.expected_content_encoding,
+                enabled = true
+            "#,
+        )
+        .bind(&target.url)
+        .bind(target.check_type.as_str())
+        .bind(target.headers.clone().map(SqlJson))
+        .bind(target.check_interval_secs)
+        .bind(target.timeout_ms)
+        .bind(target.expected_status)
+        .bind(target.follow_redirects)
+        .bind(&target.expected_body_substring)
+        .bind(&target.http_method)
+        .bind(&target.request_body)
+        .bind(&target.tags)
+        .bind(&target.expected_body_regex)
+        .bind(&target.expected_content_type)
+        .bind(&target.basic_auth_user)
+        .bind(&target.basic_auth_pass)
+        .bind(target.latency_threshold_ms)
+        .bind(&target.display_name)
+        .bind(target.max_history)
+        .bind(&target.expected_status_ranges)
+        .bind(target.metadata.clone().map(SqlJson))
+        .bind(target.insecure_skip_tls_verify)
+        .bind(target.steps.clone().map(SqlJson))
+        .bind(target.expected_headers.clone().map(SqlJson))
+        .bind(&target.cron_schedule)
+        .bind(target.ip_family.as_str())
+        .bind(&target.expected_content_encoding)
+        .execute(&mut *tx)
+        .await
+        .with_context(|| format!("failed to upsert target {}", crate::log_host(&target.url)))?;
+    }
+
+    let mut pruned = 0;
+    if file.prune {
+        let urls: Vec<&str> = targets.iter().map(|t| t.url.as_str()).collect();
+        pruned = sqlx::query(r#"UPDATE targets SET enabled = false WHERE enabled AND NOT (url = ANY($1))"#)
+            .bind(&urls)
+            .execute(&mut *tx)
+            .await
+            .context("failed to disable pruned targets")?
+            .rows_affected();
+    }
+    tx.commit().await?;
+
+    info!(path = %path.display(), upserted = targets.len(), pruned, "applied targets config");
+    Ok(())
+}
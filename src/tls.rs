@@ -0,0 +1,68 @@
+//! TLS certificate inspection for HTTPS targets.
+
+use std::time::Duration;
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use tokio::net::TcpStream;
+use tokio_native_tls::{native_tls, TlsConnector};
+
+/// Performs a TLS handshake with the target's host and returns the leaf certificate's `notAfter`.
+///
+/// Certificate validation is disabled for this probe so expired or otherwise invalid certificates
+/// still report their expiry; whether the site is reachable is judged by the regular HTTP check.
+pub async fn fetch_cert_expiry(url: &str, timeout: Duration) -> anyhow::Result<DateTime<Utc>> {
+    let url = reqwest::Url::parse(url)?;
+    let host = connect_host(&url).context("url has no host")?;
+    let port = url.port_or_known_default().unwrap_or(443);
+
+    let connector = TlsConnector::from(
+        native_tls::TlsConnector::builder()
+            .danger_accept_invalid_certs(true)
+            .build()?,
+    );
+
+    let stream = tokio::time::timeout(timeout, async {
+        let tcp = TcpStream::connect((host.as_str(), port)).await?;
+        anyhow::Ok(connector.connect(&host, tcp).await?)
+    })
+    .await
+    .context("TLS handshake timed out")??;
+
+    let cert = stream
+        .get_ref()
+        .peer_certificate()?
+        .context("server presented no certificate")?;
+    let der = cert.to_der()?;
+    let (_, parsed) = x509_parser::parse_x509_certificate(&der).context("failed to parse certificate")?;
+
+    DateTime::from_timestamp(parsed.validity().not_after.timestamp(), 0).context("certificate expiry out of range")
+}
+
+/// The URL's host as `TcpStream::connect` and the TLS connector take it: IPv6 literals without
+/// the brackets `host_str` keeps.
+fn connect_host(url: &reqwest::Url) -> Option<String> {
+    let host = url.host_str()?;
+    Some(host.strip_prefix('[').and_then(|host| host.strip_suffix(']')).unwrap_or(host).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn host(url: &str) -> Option<String> {
+        connect_host(&reqwest::Url::parse(url).unwrap())
+    }
+
+    #[test]
+    fn connect_host_strips_ipv6_brackets() {
+        assert_eq!(host("https://[::1]:8443/health").as_deref(), Some("::1"));
+        assert_eq!(host("https://[2001:db8::1]/").as_deref(), Some("2001:db8::1"));
+    }
+
+    #[test]
+    fn connect_host_keeps_names_and_ipv4() {
+        assert_eq!(host("https://example.com/").as_deref(), Some("example.com"));
+        assert_eq!(host("https://127.0.0.1:8443/").as_deref(), Some("127.0.0.1"));
+    }
+}